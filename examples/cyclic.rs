@@ -0,0 +1,48 @@
+//! Registers an automaton written in Rust and runs the game with it. Set
+//! `rule = "Cyclic"` in the config, then `cargo run --example cyclic`.
+
+use gol::game::{register, Automaton, MOORE};
+
+/// David Griffeath's cyclic cellular automaton: a cell moves on to the next
+/// of `states` states, wrapping round to 0, once `threshold` of its
+/// neighbours are already there. Random soups of every state organise into
+/// spirals.
+#[derive(Clone, Copy, Debug)]
+struct Cyclic {
+    states: u8,
+    threshold: usize,
+}
+
+impl Automaton for Cyclic {
+    type State = u8;
+
+    fn states(&self) -> u16 {
+        self.states as u16
+    }
+    fn neighbourhood(&self) -> &[(i64, i64)] {
+        &MOORE
+    }
+    fn next(&self, state: u8, neighbours: &[u8]) -> u8 {
+        let successor = ((state as u16 + 1) % self.states as u16) as u8;
+        let ready = neighbours.iter().filter(|&&n| n == successor).count();
+        if ready >= self.threshold {
+            successor
+        } else {
+            state
+        }
+    }
+}
+
+fn main() {
+    // Automata have to be registered before the config loads and looks up
+    // its rule by name.
+    let cyclic = Cyclic {
+        states: 14,
+        threshold: 1,
+    };
+    if let Err(err) = register("Cyclic", cyclic) {
+        eprintln!("{}", err);
+    }
+
+    gol::run();
+}
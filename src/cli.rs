@@ -0,0 +1,292 @@
+use crate::{
+    game::{classify, Rule, Search, SoupSettings},
+    utils::BASE_DIR,
+};
+use clap::{Parser, Subcommand};
+use serde::Serialize;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+
+#[derive(Parser, Debug)]
+#[command(version, about, long_about = None)]
+pub struct Args {
+    #[arg(short, long)]
+    pub load: Option<String>,
+
+    #[arg(short, long)]
+    pub print: Option<String>,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+// Jobs that run without opening a window.
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Runs random soups of each rule and writes how they turn out as JSON,
+    /// one rule to a line.
+    Classify {
+        /// Rulestrings, or files with a rulestring on each line.
+        #[arg(required = true)]
+        rules: Vec<String>,
+        /// Appends to this file instead of printing.
+        #[arg(short, long)]
+        output: Option<String>,
+        /// Seeds the soups, so runs can be repeated.
+        #[arg(short, long, default_value_t = 0)]
+        seed: u64,
+    },
+    /// Breeds life-like rules towards ones whose soups send out spaceships,
+    /// live long and settle down, and lists the best found.
+    Search {
+        /// Seeds the search and the soups rules are scored on.
+        #[arg(short, long, default_value_t = 0)]
+        seed: u64,
+        /// Saves progress here after every generation, and carries on from
+        /// it if it's there.
+        #[arg(short, long)]
+        checkpoint: Option<String>,
+        /// Generations of rules to breed in all, counting resumed ones.
+        #[arg(short, long, default_value_t = 50)]
+        generations: u64,
+        /// Rules in each generation.
+        #[arg(long, default_value_t = 24)]
+        population: usize,
+        /// How many of the best rules to keep.
+        #[arg(long, default_value_t = 20)]
+        hall_of_fame: usize,
+    },
+}
+
+impl Command {
+    pub fn run(self) -> Result<(), String> {
+        match self {
+            Command::Classify {
+                rules,
+                output,
+                seed,
+            } => run_classify(&rules, output.as_deref(), seed),
+            Command::Search {
+                seed,
+                checkpoint,
+                generations,
+                population,
+                hall_of_fame,
+            } => run_search(
+                seed,
+                checkpoint.as_deref(),
+                generations,
+                population,
+                hall_of_fame,
+            ),
+        }
+    }
+}
+
+/// A rule that couldn't be classified, listed in the output with the ones
+/// that could so that none go missing.
+#[derive(Serialize)]
+struct Skipped<'a> {
+    rule: &'a str,
+    skipped: String,
+}
+
+fn run_classify(rules: &[String], output: Option<&str>, seed: u64) -> Result<(), String> {
+    let settings = SoupSettings::from_config(seed);
+    let write = |line: String| match output {
+        Some(path) => append_line(path, &line),
+        None => {
+            println!("{}", line);
+            Ok(())
+        }
+    };
+
+    let mut rulestrings = Vec::new();
+    for rule in rules {
+        match Path::new(rule).is_file() {
+            true => rulestrings.extend(read_rulestrings(rule)?),
+            false => rulestrings.push(rule.clone()),
+        }
+    }
+    for rulestring in &rulestrings {
+        let result = rulestring
+            .parse::<Rule>()
+            .map_err(|err| err.to_string())
+            .and_then(|rule| classify(&rule, &settings));
+        let line = match result {
+            Ok(classification) => serde_json::to_string(&classification),
+            Err(skipped) => serde_json::to_string(&Skipped {
+                rule: rulestring,
+                skipped,
+            }),
+        };
+        write(line.unwrap())?;
+    }
+
+    Ok(())
+}
+
+fn run_search(
+    seed: u64,
+    checkpoint: Option<&str>,
+    generations: u64,
+    population: usize,
+    hall_of_fame: usize,
+) -> Result<(), String> {
+    let mut search = match checkpoint.filter(|path| Path::new(path).is_file()) {
+        Some(path) => {
+            let search = load_checkpoint(path)?;
+            if search.seed != seed {
+                eprintln!(
+                    "Using the checkpoint's seed {} instead of {}",
+                    search.seed, seed
+                );
+            }
+            if search.population.len() != population.max(2) {
+                eprintln!(
+                    "Using the checkpoint's population of {} instead of {}",
+                    search.population.len(),
+                    population.max(2)
+                );
+            }
+            if search.settings != SoupSettings::from_config(search.seed) {
+                eprintln!("Using the checkpoint's soup settings instead of the config's");
+            }
+            eprintln!("Resuming from generation {}", search.generation);
+            search
+        }
+        // The same soups for every rule, so their scores compare.
+        None => Search::new(seed, SoupSettings::from_config(seed), population.max(2)),
+    };
+
+    while search.generation < generations {
+        search.step(hall_of_fame);
+        if let Some(path) = checkpoint {
+            save_checkpoint(&search, path)?;
+        }
+        if let Some(best) = search.hall_of_fame.first() {
+            eprintln!(
+                "Generation {}: best {} ({:.2}) of {} rules tried",
+                search.generation,
+                best.classification.rule,
+                best.fitness,
+                search.tried()
+            );
+        }
+    }
+
+    for entry in &search.hall_of_fame {
+        let classification = &entry.classification;
+        println!(
+            "{:.2}  {}  {}, gliders in {} of {} soups",
+            entry.fitness,
+            classification.rule,
+            classification.behaviour,
+            classification.gliders,
+            classification.soups
+        );
+    }
+
+    Ok(())
+}
+
+fn load_checkpoint(path: &str) -> Result<Search, String> {
+    let text = fs::read_to_string(path)
+        .map_err(|err| format!("Failed to read checkpoint {}: {}", path, err))?;
+    serde_json::from_str(&text).map_err(|err| format!("Invalid checkpoint {}: {}", path, err))
+}
+
+/// Writes next to the checkpoint and then moves over it, so stopping a
+/// search halfway through saving doesn't lose it.
+fn save_checkpoint(search: &Search, path: &str) -> Result<(), String> {
+    let temporary = format!("{}.tmp", path);
+    fs::write(&temporary, serde_json::to_string(search).unwrap())
+        .and_then(|_| fs::rename(&temporary, path))
+        .map_err(|err| format!("Failed to save checkpoint {}: {}", path, err))
+}
+
+/// The rulestrings in a file, skipping blank lines and `#` comments.
+fn read_rulestrings(path: &str) -> Result<Vec<String>, String> {
+    let text = fs::read_to_string(path)
+        .map_err(|err| format!("Failed to read rules from {}: {}", path, err))?;
+
+    Ok(text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(String::from)
+        .collect())
+}
+
+pub fn append_line(path: &str, line: &str) -> Result<(), String> {
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| writeln!(file, "{}", line))
+        .map_err(|err| format!("Failed to write to {}: {}", path, err))
+}
+
+/// Where classifications exported from the app go, one JSON object a line.
+pub fn classifications_path() -> String {
+    BASE_DIR.to_string() + "/classifications.jsonl"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Rule;
+
+    fn settings(seed: u64) -> SoupSettings {
+        SoupSettings {
+            soups: 3,
+            size: 16,
+            density: 0.35,
+            generations: 100,
+            seed,
+        }
+    }
+
+    fn results(search: &Search) -> (Vec<Rule>, Vec<(String, f64)>, usize) {
+        let hall_of_fame = search
+            .hall_of_fame
+            .iter()
+            .map(|entry| (entry.classification.rule.clone(), entry.fitness))
+            .collect();
+        (search.population.clone(), hall_of_fame, search.tried())
+    }
+
+    #[test]
+    fn resumed_searches_carry_on_as_if_never_stopped() {
+        let mut uninterrupted = Search::new(5, settings(5), 8);
+        for _ in 0..4 {
+            uninterrupted.step(5);
+        }
+
+        let path = std::env::temp_dir().join(format!("gol-search-{}.json", std::process::id()));
+        let path = path.to_str().unwrap();
+        let mut search = Search::new(5, settings(5), 8);
+        for _ in 0..2 {
+            search.step(5);
+        }
+        save_checkpoint(&search, path).unwrap();
+        let mut resumed = load_checkpoint(path).unwrap();
+        fs::remove_file(path).unwrap();
+        for _ in 0..2 {
+            resumed.step(5);
+        }
+
+        assert_eq!(resumed.generation, 4);
+        assert_eq!(results(&resumed), results(&uninterrupted));
+    }
+
+    #[test]
+    fn searches_depend_on_their_seed() {
+        let mut a = Search::new(5, settings(5), 8);
+        let mut b = Search::new(6, settings(6), 8);
+        a.step(5);
+        b.step(5);
+        assert_ne!(a.population, b.population);
+    }
+}
@@ -25,6 +25,13 @@ impl Color {
             b: b as f32 / 255.0,
         }
     }
+    pub fn lerp(self, other: Self, t: f32) -> Self {
+        Self::new(
+            self.r + (other.r - self.r) * t,
+            self.g + (other.g - self.g) * t,
+            self.b + (other.b - self.b) * t,
+        )
+    }
     pub fn hex(hex: u32) -> Self {
         Self::new_u8(
             (hex >> 16) as u8,
@@ -1,6 +1,10 @@
 use std::{env, fs};
 
-use crate::{prelude::notify_info, utils::BASE_DIR};
+use crate::{
+    game::Rule,
+    prelude::{notify_error, notify_info},
+    utils::BASE_DIR,
+};
 
 use super::Config;
 
@@ -10,7 +14,18 @@ pub fn load() -> Config {
     let text = fs::read_to_string(&config_path);
 
     if let Ok(text) = text {
-        return toml::from_str(text.as_str()).unwrap_or(Config::default());
+        match parse(&text) {
+            Ok((config, rule_error)) => {
+                if let Some(err) = rule_error {
+                    notify_error(format!("Invalid rule in config file: {}", err));
+                }
+                config
+            }
+            Err(err) => {
+                notify_error(format!("Failed to load config file: {}", err.message()));
+                Config::default()
+            }
+        }
     } else {
         notify_info("Failed to read config file; using default values.");
 
@@ -32,9 +47,50 @@ pub fn load() -> Config {
     }
 }
 
+/// Parses the config file. A rule that doesn't parse, like the name of a rule
+/// file that's gone, leaves the rule at its default and comes back as the
+/// second value rather than failing the whole file.
+fn parse(text: &str) -> Result<(Config, Option<String>), toml::de::Error> {
+    let mut table: toml::Table = toml::from_str(text)?;
+    let rule = table.remove("rule");
+    let mut config: Config = table.try_into()?;
+
+    let mut rule_error = None;
+    match rule.map(|rule| rule.try_into::<Rule>()) {
+        Some(Ok(rule)) => config.rule = rule,
+        Some(Err(err)) => rule_error = Some(err.message().to_string()),
+        None => {}
+    }
+
+    Ok((config, rule_error))
+}
+
 fn config_path() -> String {
     let path = BASE_DIR.to_string() + "/config.toml";
     dbg!(&path);
 
     path
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn invalid_rules_only_lose_the_rule() {
+        let (config, error) = parse("rule = 'NoSuchRuleFile'\ntile_size = 7.0\n").unwrap();
+        assert!(error.unwrap().contains("Unknown rule 'NoSuchRuleFile'"));
+        assert_eq!(config.rule, Config::default().rule);
+        assert_eq!(config.tile_size, 7.0);
+
+        let (config, error) = parse("rule = 'B36/S23'\ntile_size = 7.0\n").unwrap();
+        assert!(error.is_none());
+        assert_eq!(config.rule, "B36/S23".parse().unwrap());
+    }
+
+    #[test]
+    fn other_errors_still_fail_the_file() {
+        assert!(parse("rule = 'B3/S23'\ntile_size = 'big'\n").is_err());
+        assert!(parse("tile_size = ").is_err());
+    }
+}
@@ -1,5 +1,8 @@
 use self::load::load;
-use crate::{game::Rule, utils::VecU2};
+use crate::game::{
+    ContinuousPreset, ContinuousRule, EngineKind, Rule, Topology, CONDUCTOR, ELECTRON_HEAD,
+    ELECTRON_TAIL,
+};
 pub use color::*;
 use lazy_static::lazy_static;
 use serde::{Deserialize, Serialize};
@@ -12,12 +15,21 @@ lazy_static! {
 }
 
 #[derive(Deserialize, Serialize)]
+#[serde(default)]
 pub struct Config {
     pub window_title: String,
     pub tile_size: f32,
     pub background_color: Color,
     pub void_color: Color,
     pub cell_color: Color,
+    /// Colours of the decay states of Generations rules, starting at state 2.
+    /// States past the end of the list fade towards the background.
+    pub decay_colors: Vec<Color>,
+    /// Colours of the electron heads, electron tails and conductors of
+    /// WireWorld.
+    pub wireworld_head_color: Color,
+    pub wireworld_tail_color: Color,
+    pub wireworld_conductor_color: Color,
     pub cell_color_highlighted: Color,
     pub background_color_highlighted: Color,
     pub zoom_speed: f32,
@@ -27,10 +39,45 @@ pub struct Config {
     pub pan_speed: f32,
     pub text_color: Color,
     pub smoothing_factor: f32,
+    /// The rule the app starts with. Press Enter to switch while it runs.
     pub rule: Rule,
-    pub autosize_board: bool,
-    pub board_size: VecU2,
+    pub topology: Topology,
     pub parallel_board_processing: bool,
+    pub engine: EngineKind,
+    pub hashlife_step: u32,
+    /// Seed for the random numbers of stochastic rules. Every board picks its
+    /// own when unset.
+    pub seed: Option<u64>,
+    /// Runs the continuous automaton of this name from `continuous_presets`
+    /// instead of `rule`.
+    pub continuous: Option<String>,
+    pub continuous_presets: Vec<ContinuousPreset>,
+    /// Cells along each side of the wrapping field continuous automata run
+    /// on, rounded up to a power of two.
+    pub field_size: usize,
+    /// Colours continuous cells go through from 0 to 1.
+    pub field_colors: Vec<Color>,
+    /// Colours of masked cells: walls that are always alive, walls that are
+    /// always dead, and emitters.
+    pub wall_on_color: Color,
+    pub wall_off_color: Color,
+    pub clock_color: Color,
+    /// How many generations apart newly painted emitters switch on.
+    pub clock_period: u64,
+    /// Rules selections can be painted with, to run part of the board under
+    /// a different rule.
+    pub region_rules: Vec<Rule>,
+    /// Tints of the rule regions, one for each rule on the board in turn.
+    pub region_colors: Vec<Color>,
+    pub region_opacity: f32,
+    /// Side of the square of random cells the rule explorer runs each rule
+    /// on, and the chance of each of them starting alive.
+    pub soup_size: u32,
+    pub soup_density: f64,
+    /// How many soups rules are classified on, and how many generations each
+    /// runs for at most.
+    pub classify_soups: usize,
+    pub classify_generations: u64,
     pub selection_color: Color,
     pub selection_thickness: f32,
     pub font_size: u32,
@@ -41,14 +88,91 @@ pub struct Config {
 
 impl Config {
     pub fn load() -> Self {
+        // Tests run on the defaults, whatever config the machine has.
+        if cfg!(test) {
+            return Config::default();
+        }
         load()
     }
-    pub fn default() -> Self {
+    /// The topology from the rulestring suffix, falling back to `topology`.
+    pub fn topology(&self) -> Topology {
+        self.rule.topology.unwrap_or(self.topology)
+    }
+    /// The continuous rule `continuous` names, if any.
+    pub fn continuous_rule(&self) -> Option<Result<&ContinuousRule, String>> {
+        let name = self.continuous.as_ref()?;
+        Some(
+            self.continuous_presets
+                .iter()
+                .find(|preset| &preset.name == name)
+                .map(|preset| &preset.rule)
+                .ok_or_else(|| format!("There's no continuous preset called '{}'", name)),
+        )
+    }
+    /// The colour of a cell under `rule`. WireWorld and rule files have their
+    /// own.
+    pub fn state_color(&self, rule: &Rule, state: u8) -> Color {
+        if let Some([r, g, b]) = rule.color(state) {
+            return Color::new_u8(r, g, b);
+        }
+        if rule.is_wireworld() {
+            return match state {
+                ELECTRON_HEAD => self.wireworld_head_color,
+                ELECTRON_TAIL => self.wireworld_tail_color,
+                CONDUCTOR => self.wireworld_conductor_color,
+                _ => self.background_color,
+            };
+        }
+
+        match state {
+            0 => self.background_color,
+            1 => self.cell_color,
+            _ => self
+                .decay_colors
+                .get(state as usize - 2)
+                .copied()
+                .unwrap_or_else(|| {
+                    let last = self.decay_colors.last().copied().unwrap_or(self.cell_color);
+                    let t = (state as f32 - 1.) / (rule.states() as f32 - 1.).max(1.);
+                    last.lerp(self.background_color, t)
+                }),
+        }
+    }
+    /// The colour of a continuous cell, blending between `field_colors`.
+    pub fn field_color(&self, value: f32) -> Color {
+        let Some(&last) = self.field_colors.last() else {
+            return self.cell_color.lerp(self.background_color, 1. - value);
+        };
+
+        let position = value.clamp(0., 1.) * (self.field_colors.len() - 1) as f32;
+        let i = position as usize;
+        match self.field_colors.get(i + 1) {
+            Some(&next) => self.field_colors[i].lerp(next, position.fract()),
+            None => last,
+        }
+    }
+    pub fn to_toml(&self) -> String {
+        toml::to_string(&self).unwrap()
+    }
+}
+
+/// Fields missing from the config file take these values, so config files
+/// from older versions keep working.
+impl Default for Config {
+    fn default() -> Self {
         Self {
             window_title: String::from("Game of Life"),
             tile_size: 10.0,
             background_color: Color::new(0.1, 0.1, 0.1),
             cell_color: Color::new(0.9, 0.9, 0.9),
+            decay_colors: vec![
+                Color::hex(0x4ba4f2),
+                Color::hex(0x3479b5),
+                Color::hex(0x225178),
+            ],
+            wireworld_head_color: Color::hex(0x0080ff),
+            wireworld_tail_color: Color::hex(0xffffff),
+            wireworld_conductor_color: Color::hex(0xff8000),
             cell_color_highlighted: Color::new(0.8, 0.8, 0.8),
             background_color_highlighted: Color::new(0.2, 0.2, 0.2),
             zoom_speed: 1.0,
@@ -59,10 +183,36 @@ impl Config {
             void_color: Color::new(0.08, 0.08, 0.08),
             text_color: Color::new(0.95, 0.95, 0.95),
             smoothing_factor: 3.0,
-            rule: Rule::from_str("23/3"),
-            autosize_board: false,
-            board_size: VecU2::new(700, 700),
+            rule: "B3/S23".parse().unwrap(),
+            topology: Topology::Plane,
             parallel_board_processing: true,
+            engine: EngineKind::Classic,
+            hashlife_step: 0,
+            seed: None,
+            continuous: None,
+            continuous_presets: ContinuousRule::presets(),
+            field_size: 512,
+            field_colors: vec![
+                Color::new(0.1, 0.1, 0.1),
+                Color::hex(0x225178),
+                Color::hex(0x4ba4f2),
+                Color::hex(0xf2e94b),
+            ],
+            wall_on_color: Color::hex(0xb0b0b0),
+            wall_off_color: Color::hex(0x404040),
+            clock_color: Color::hex(0xf28b4b),
+            clock_period: 8,
+            region_rules: vec!["B36/S23".parse().unwrap(), "B3/S12345".parse().unwrap()],
+            region_colors: vec![
+                Color::hex(0xf2e94b),
+                Color::hex(0xcc6b70),
+                Color::hex(0x51e98a),
+            ],
+            region_opacity: 0.15,
+            soup_size: 64,
+            soup_density: 0.5,
+            classify_soups: 16,
+            classify_generations: 2000,
             selection_color: Color::hex(0x4ba4f2),
             selection_thickness: 4.0,
             font_size: 24,
@@ -71,7 +221,4 @@ impl Config {
             error_color: Color::hex(0xcc6b70),
         }
     }
-    pub fn to_toml(&self) -> String {
-        toml::to_string(&self).unwrap()
-    }
 }
@@ -0,0 +1,35 @@
+use crate::{game::Rule, utils::BASE_DIR};
+use std::fs;
+
+/// Adds `rule` to the favourites in the data directory, a plain list with a
+/// rulestring on each line. Returns whether it wasn't there already.
+pub fn star_rule(rule: &Rule) -> Result<bool, String> {
+    let rulestring = rule.serialize();
+    let mut favourites = starred_rules();
+    if favourites.contains(&rulestring) {
+        return Ok(false);
+    }
+
+    favourites.push(rulestring);
+    fs::create_dir_all(&*BASE_DIR)
+        .and_then(|_| fs::write(favourites_path(), favourites.join("\n") + "\n"))
+        .map_err(|err| format!("Failed to save favourite rules: {}", err))?;
+    Ok(true)
+}
+
+/// The rulestrings starred so far, oldest first.
+pub fn starred_rules() -> Vec<String> {
+    fs::read_to_string(favourites_path())
+        .map(|text| {
+            text.lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .map(String::from)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+pub fn favourites_path() -> String {
+    BASE_DIR.to_string() + "/favourite_rules.txt"
+}
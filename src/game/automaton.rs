@@ -0,0 +1,183 @@
+use lazy_static::lazy_static;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, RwLock};
+
+/// How far away neighbours of a registered automaton can be, so that a chunk
+/// and a border this wide hold every cell it looks at.
+pub const MAX_REACH: i64 = 4;
+const MAX_NEIGHBOURS: usize = ((2 * MAX_REACH + 1) * (2 * MAX_REACH + 1) - 1) as usize;
+
+/// The neighbours of life-like rules: NW, N, NE, W, E, SW, S, SE, in the same
+/// order as neighbour patterns from the most significant bit.
+pub const MOORE: [(i64, i64); 8] = [
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+    (-1, 0),
+    (1, 0),
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+];
+
+/// The state of a cell. Boards, the renderer and savestates all store states
+/// as `u8`s, with 0 for the empty background.
+pub trait CellState: Copy + Send + Sync + 'static {
+    fn from_u8(state: u8) -> Self;
+    fn to_u8(self) -> u8;
+}
+
+impl CellState for u8 {
+    fn from_u8(state: u8) -> Self {
+        state
+    }
+    fn to_u8(self) -> u8 {
+        self
+    }
+}
+
+impl CellState for bool {
+    fn from_u8(state: u8) -> Self {
+        state == 1
+    }
+    fn to_u8(self) -> u8 {
+        self as u8
+    }
+}
+
+/// A cellular automaton whose next state only depends on a cell and a fixed
+/// set of neighbours around it. Implement this and [`register`] it to run
+/// it by name like any other rule.
+pub trait Automaton: Send + Sync + 'static {
+    type State: CellState;
+
+    /// How many states cells go through, at most 256.
+    fn states(&self) -> u16;
+    /// Offsets of the neighbours from the cell, `y` pointing up. They're
+    /// passed to [`Automaton::next`] in this order.
+    fn neighbourhood(&self) -> &[(i64, i64)];
+    fn next(&self, state: Self::State, neighbours: &[Self::State]) -> Self::State;
+    /// The colour of `state`, if it shouldn't come from the config.
+    fn color(&self, _state: Self::State) -> Option<[u8; 3]> {
+        None
+    }
+}
+
+/// An [`Automaton`] working on the `u8`s boards store, so that automata with
+/// different state types can sit behind the same pointer.
+pub trait DynAutomaton: Send + Sync {
+    fn states(&self) -> u16;
+    fn neighbourhood(&self) -> &[(i64, i64)];
+    /// The next state of a cell, given the state of each neighbour by index.
+    fn step(&self, state: u8, neighbour: &dyn Fn(usize) -> u8) -> u8;
+    fn color(&self, state: u8) -> Option<[u8; 3]>;
+}
+
+impl<A: Automaton> DynAutomaton for A {
+    fn states(&self) -> u16 {
+        Automaton::states(self)
+    }
+    fn neighbourhood(&self) -> &[(i64, i64)] {
+        Automaton::neighbourhood(self)
+    }
+    fn step(&self, state: u8, neighbour: &dyn Fn(usize) -> u8) -> u8 {
+        let mut neighbours = [A::State::from_u8(0); MAX_NEIGHBOURS];
+        let count = Automaton::neighbourhood(self).len();
+        for (i, slot) in neighbours[..count].iter_mut().enumerate() {
+            *slot = A::State::from_u8(neighbour(i));
+        }
+        self.next(A::State::from_u8(state), &neighbours[..count])
+            .to_u8()
+    }
+    fn color(&self, state: u8) -> Option<[u8; 3]> {
+        Automaton::color(self, A::State::from_u8(state))
+    }
+}
+
+/// An automaton from the registry, with the name rules refer to it by.
+#[derive(Clone)]
+pub struct NamedAutomaton {
+    pub name: String,
+    pub automaton: Arc<dyn DynAutomaton>,
+    /// The furthest any neighbour is along either axis.
+    pub reach: u8,
+}
+
+impl PartialEq for NamedAutomaton {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+    }
+}
+
+impl Eq for NamedAutomaton {}
+
+impl fmt::Debug for NamedAutomaton {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("NamedAutomaton").field(&self.name).finish()
+    }
+}
+
+lazy_static! {
+    static ref REGISTRY: RwLock<HashMap<String, NamedAutomaton>> = RwLock::new(HashMap::new());
+}
+
+/// Makes `automaton` available as the rule `name`, ignoring case. Rules are
+/// parsed when the config loads, so register automata before that, at the
+/// start of `main`.
+pub fn register(name: &str, automaton: impl Automaton) -> Result<(), String> {
+    let neighbourhood = Automaton::neighbourhood(&automaton);
+    if !(2..=256).contains(&Automaton::states(&automaton)) {
+        return Err(format!("{}: State count must be 2-256", name));
+    }
+    if neighbourhood.len() > MAX_NEIGHBOURS {
+        return Err(format!(
+            "{}: Automata can have at most {} neighbours",
+            name, MAX_NEIGHBOURS
+        ));
+    }
+    if neighbourhood.contains(&(0, 0)) {
+        return Err(format!("{}: Cells can't be their own neighbours", name));
+    }
+    let reach = neighbourhood
+        .iter()
+        .map(|&(dx, dy)| dx.abs().max(dy.abs()))
+        .max()
+        .unwrap_or(1)
+        .max(1);
+    if reach > MAX_REACH {
+        return Err(format!(
+            "{}: Neighbours can be at most {} cells away",
+            name, MAX_REACH
+        ));
+    }
+    if automaton.step(0, &|_| 0) != 0 {
+        return Err(format!(
+            "{}: Automata where empty space comes alive aren't supported",
+            name
+        ));
+    }
+
+    let mut registry = REGISTRY.write().unwrap();
+    let key = name.to_lowercase();
+    if registry.contains_key(&key) {
+        return Err(format!(
+            "{}: An automaton is already registered by that name",
+            name
+        ));
+    }
+    registry.insert(
+        key,
+        NamedAutomaton {
+            name: name.to_string(),
+            automaton: Arc::new(automaton),
+            reach: reach as u8,
+        },
+    );
+    Ok(())
+}
+
+/// The automaton registered as `name`, ignoring case.
+pub fn registered(name: &str) -> Option<NamedAutomaton> {
+    REGISTRY.read().unwrap().get(&name.to_lowercase()).cloned()
+}
@@ -0,0 +1,106 @@
+/// A rule on the 2x2 blocks of a Margolus partition, which moves one block
+/// over every generation so the blocks overlap those of the last one.
+///
+/// Blocks are numbered like MCell, adding 1 for the upper left cell, 2 for
+/// the upper right, 4 for the lower left and 8 for the lower right.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BlockRule {
+    /// What every block turns into. Always a permutation, so every
+    /// generation can be undone.
+    blocks: [u8; 16],
+}
+
+/// Rules that have a name of their own, as written in MCell.
+const PRESETS: [(&str, [u8; 16]); 3] = [
+    (
+        "Critters",
+        [15, 14, 13, 3, 11, 5, 6, 1, 7, 9, 10, 2, 12, 4, 8, 0],
+    ),
+    (
+        "Tron",
+        [15, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 0],
+    ),
+    (
+        "BBM",
+        [0, 8, 4, 3, 2, 5, 9, 7, 1, 6, 10, 11, 12, 13, 14, 15],
+    ),
+];
+
+impl BlockRule {
+    /// What `block` turns into.
+    pub fn next(&self, block: u8) -> u8 {
+        self.blocks[block as usize]
+    }
+
+    /// The rule that undoes this one.
+    pub fn inverse(&self) -> Self {
+        let mut blocks = [0; 16];
+        for (block, &next) in self.blocks.iter().enumerate() {
+            blocks[next as usize] = block as u8;
+        }
+        BlockRule { blocks }
+    }
+
+    /// Whether empty blocks fill up, in which case full ones empty again and
+    /// the infinite background strobes.
+    pub fn strobes(&self) -> bool {
+        self.blocks[0] == 15
+    }
+
+    /// The rule on boards stored inverted on generations where the background
+    /// is alive, like B0 rules. `from` and `to` are whether it's alive on
+    /// this generation and the next.
+    pub fn emulated(&self, from: bool, to: bool) -> Self {
+        let (from, to) = (from as u8 * 15, to as u8 * 15);
+        BlockRule {
+            blocks: std::array::from_fn(|block| self.blocks[block ^ from as usize] ^ to),
+        }
+    }
+
+    pub fn preset(name: &str) -> Option<Self> {
+        PRESETS
+            .iter()
+            .find(|(preset, _)| preset.eq_ignore_ascii_case(name))
+            .map(|&(_, blocks)| BlockRule { blocks })
+    }
+
+    /// Parses the blocks of an MCell rulestring like `MS,D0;8;4;3;2;5;9;7;1;6;10;11;12;13;14;15`,
+    /// without the `MS,D`.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let numbers = text
+            .split(';')
+            .map(|n| n.trim().parse::<u8>().ok().filter(|&n| n < 16))
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(|| format!("Invalid block '{}': blocks are numbered 0-15", text))?;
+        let blocks: [u8; 16] = numbers
+            .try_into()
+            .map_err(|_| String::from("Block rules need 16 blocks"))?;
+
+        let mut seen = [false; 16];
+        for &block in &blocks {
+            if std::mem::replace(&mut seen[block as usize], true) {
+                return Err(format!(
+                    "Block {} comes up twice, so the rule isn't reversible",
+                    block
+                ));
+            }
+        }
+        if blocks[0] != 0 && (blocks[0] != 15 || blocks[15] != 0) {
+            return Err(String::from(
+                "Empty blocks have to stay empty, or fill up and empty again",
+            ));
+        }
+
+        Ok(BlockRule { blocks })
+    }
+
+    pub fn serialize(&self) -> String {
+        match PRESETS.iter().find(|(_, blocks)| *blocks == self.blocks) {
+            Some((name, _)) => name.to_string(),
+            None => format!(
+                "MS,D{}",
+                self.blocks.map(|block| block.to_string()).join(";")
+            ),
+        }
+    }
+}
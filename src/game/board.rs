@@ -1,235 +1,543 @@
 use crate::prelude::*;
-use rayon::prelude::*;
-use std::collections::HashSet;
+use rand::Rng;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
 
-use crate::{config::CONFIG, game_logic::i_to_xy};
+use crate::config::CONFIG;
 
+pub type ChunkMap = HashMap<(i64, i64), Chunk>;
+
+/// An unbounded universe. Only chunks that contain live cells are stored, so
+/// the board grows and shrinks with the pattern on it.
 #[derive(Clone, Debug)]
 pub struct Board {
-    pub tiles: Grid<bool>,
+    pub chunks: ChunkMap,
+    pub generation: u64,
+    pub topology: Topology,
+    /// Chunks that changed in the last generation or were edited since.
+    /// `None` means everything has to be recomputed.
+    pub dirty: Option<HashSet<(i64, i64)>>,
+    /// How many chunks the engine looked at in the last generation.
+    pub active_chunks: usize,
+    /// Seeds the random numbers of stochastic rules, so runs replay exactly.
+    pub seed: u64,
+    /// Rectangles that run under a rule of their own, later ones covering
+    /// earlier ones. Everywhere else runs under the board's rule.
+    pub regions: Vec<Region>,
+    /// Cells the rule can't change, forced back into their state after every
+    /// generation.
+    pub mask: HashMap<(i64, i64), Mask>,
+    engine: SharedEngine,
 }
 
 impl Board {
-    pub fn new(width: usize, height: usize) -> Self {
-        let tiles = Grid::from_vec(vec![false; width * height], width);
-
-        Self { tiles }
-    }
-    pub fn advance(&mut self) {
-        if !CONFIG.parallel_board_processing {
-            let width = self.width();
-            let rule = &CONFIG.rule;
-
-            let mut next_tiles = self.clone();
-
-            let mut active_tiles = self
-                .tiles
-                .iter()
-                .enumerate()
-                .filter(|(_, v)| **v)
-                .map(|(i, _)| i_to_xy(width, i))
-                .collect::<HashSet<_>>();
-
-            active_tiles.clone().iter().for_each(|&(x, y)| {
-                [
-                    [1, 1],
-                    [1, 0],
-                    [0, 1],
-                    [-1, 0],
-                    [0, -1],
-                    [-1, -1],
-                    [-1, 1],
-                    [1, -1],
-                ]
-                .iter()
-                .for_each(|[dx, dy]| {
-                    active_tiles.insert(((dx + x as isize) as usize, (dy + y as isize) as usize));
-                })
-            });
-
-            for (x, y) in active_tiles.into_iter() {
-                let count = self.count_neighbors(x, y);
-                let cell = self.get(x, y).unwrap_or(false);
-                next_tiles.set(
-                    x,
-                    y,
-                    (!cell && rule.born(count)) || (cell && rule.survive(count)),
-                );
-            }
+    pub fn new() -> Self {
+        Self {
+            chunks: HashMap::new(),
+            generation: 0,
+            topology: CONFIG.topology(),
+            dirty: None,
+            active_chunks: 0,
+            seed: CONFIG.seed.unwrap_or_else(rand::random),
+            regions: Vec::new(),
+            mask: HashMap::new(),
+            engine: CONFIG.engine.build(),
+        }
+    }
+    /// An empty board simulated by `engine` instead of the configured one.
+    pub fn with_engine(engine: impl Engine + 'static) -> Self {
+        Self {
+            engine: Arc::new(Mutex::new(engine)),
+            ..Self::new()
+        }
+    }
+    /// Places `tiles` so that its centre lands on the origin.
+    pub fn from_grid(tiles: Grid<u8>) -> Self {
+        let mut board = Self::new();
+        let pos = VecI2::new(-(tiles.cols() as i64 / 2), -(tiles.rows() as i64 / 2));
+        board.set_area(pos, &tiles);
+
+        board
+    }
+    /// Runs the board under `main_rule` (and its regions under theirs) for a
+    /// generation, or as many as the engine takes at once.
+    pub fn advance(&mut self, main_rule: &Rule) {
+        let engine = self.engine.clone();
+        let mut engine = engine.lock().unwrap();
+
+        let rule = main_rule.emulated(self.generation, self.topology);
+        // Chunks that stood still last generation may not under a different rule.
+        if self.generation > 0 && rule != main_rule.emulated(self.generation - 1, self.topology) {
+            self.dirty = None;
+        }
 
-            *self = next_tiles;
+        // Masks have to be applied every generation and regions run more
+        // than one rule, which only the fallback engines do.
+        let generations = if self.regions.is_empty()
+            && self.mask.is_empty()
+            && engine.supports(main_rule, self.topology)
+        {
+            engine.advance(self, &rule)
         } else {
-            let width = self.width();
-            let height = self.height();
-            let rule = &CONFIG.rule;
+            fallback(main_rule).advance(self, &rule)
+        };
 
-            let mut next_tiles = vec![false; width * height];
+        self.generation += generations;
+        self.apply_mask(main_rule);
+    }
+    /// Undoes the last generation, for block rules, which are reversible.
+    /// Returns whether it could.
+    pub fn step_back(&mut self, main_rule: &Rule) -> bool {
+        if self.generation == 0 {
+            return false;
+        }
+        let rule = main_rule.emulated(self.generation - 1, self.topology);
+        let Some(blocks) = rule.blocks() else {
+            return false;
+        };
 
-            next_tiles.par_iter_mut().enumerate().for_each(|(i, tile)| {
-                let (x, y) = self.i_to_xy(i);
-                let count = self.count_neighbors(x, y);
-                let cell = self.get(x, y).unwrap_or(false);
-                *tile = (!cell && rule.born(count)) || (cell && rule.survive(count));
-            });
+        step_blocks(self, &blocks.inverse(), (self.generation - 1) % 2 == 1);
+        self.generation -= 1;
+        self.apply_mask(main_rule);
+        true
+    }
+    /// Gets the board ready to run under `to` instead of `from` from the
+    /// generation it's on, switching to the topology of `to` if it has one.
+    pub fn change_rule(&mut self, from: &Rule, to: &Rule) -> Result<(), String> {
+        if !self.regions.is_empty() {
+            if let Some(err) = region_error(to) {
+                return Err(err);
+            }
+        }
+        let topology = to.topology.unwrap_or(self.topology);
+        // The stored board is inverted while the background is alive, which
+        // can't be undone on an unbounded plane.
+        if from.background_alive(self.generation, self.topology) {
+            if !to.background_alive(self.generation, topology) {
+                return Err(format!(
+                    "{}: The background is alive on this generation, so the board can't leave the rule",
+                    from.serialize()
+                ));
+            }
+        } else if to.background_alive(self.generation, topology) {
+            // B0 rules never store generation 0 inverted.
+            self.generation = 0;
+        }
 
-            self.tiles = Grid::from_vec(next_tiles, width);
+        if topology != self.topology {
+            self.topology = topology;
+            let outside: Vec<(i64, i64)> = self
+                .cells()
+                .map(|(x, y, _)| (x, y))
+                .filter(|&(x, y)| !topology.contains(x, y))
+                .collect();
+            for (x, y) in outside {
+                self.set_state(x, y, 0);
+            }
+            self.mask.retain(|&(x, y), _| topology.contains(x, y));
         }
+
+        self.dirty = None;
+        self.apply_mask(to);
+        Ok(())
     }
-    pub fn width(&self) -> usize {
-        self.tiles.cols()
+    /// The region whose rule the cell runs under, if it's in one.
+    pub fn region_at(&self, x: i64, y: i64) -> Option<&Region> {
+        self.regions
+            .iter()
+            .rev()
+            .find(|region| region.contains(x, y))
     }
-    pub fn height(&self) -> usize {
-        self.tiles.rows()
+    /// Runs the cells of `region` under its rule from now on, instead of
+    /// `main_rule`.
+    pub fn paint_region(&mut self, region: Region, main_rule: &Rule) -> Result<(), String> {
+        if let Some(err) = region_error(main_rule).or_else(|| region_error(&region.rule)) {
+            return Err(err);
+        }
+
+        self.regions.push(region);
+        self.dirty = None;
+        Ok(())
     }
-    pub fn get(&self, x: usize, y: usize) -> Option<bool> {
-        self.tiles.get(y, x).cloned()
+    /// Hands every region touching the rectangle back to the board's rule.
+    pub fn clear_regions(&mut self, min: (i64, i64), max: (i64, i64)) {
+        self.regions.retain(|region| !region.overlaps(min, max));
+        self.dirty = None;
     }
-    pub fn get_or_empty(&self, x: isize, y: isize) -> bool {
-        if x < 0 || y < 0 {
-            return false;
+    /// Masks the cell, putting it straight into its masked state under
+    /// `main_rule`, or unmasks it with `None`.
+    pub fn set_mask(&mut self, x: i64, y: i64, mask: Option<Mask>, main_rule: &Rule) {
+        match mask {
+            Some(mask) if self.topology.contains(x, y) => {
+                self.mask.insert((x, y), mask);
+                self.hold(x, y, mask, main_rule);
+            }
+            Some(_) => {}
+            None => {
+                self.mask.remove(&(x, y));
+            }
+        }
+    }
+    pub fn clear_mask(&mut self) {
+        self.mask.clear();
+    }
+    /// Forces every masked cell into its state on the current generation.
+    fn apply_mask(&mut self, main_rule: &Rule) {
+        let cells = self
+            .mask
+            .iter()
+            .map(|(&cell, &mask)| (cell, mask))
+            .collect::<Vec<_>>();
+
+        for ((x, y), mask) in cells {
+            self.hold(x, y, mask, main_rule);
         }
+    }
+    /// Puts a masked cell into its state on the current generation.
+    fn hold(&mut self, x: i64, y: i64, mask: Mask, main_rule: &Rule) {
+        let state = mask.state(self.generation);
+        // B0 rules store these generations inverted.
+        let state = match main_rule.background_alive(self.generation, self.topology) {
+            true => (state == 0) as u8,
+            false => state,
+        };
+        if self.state(x, y) != state {
+            self.set_state(x, y, state);
+        }
+    }
+    /// Whether the cell is alive, i.e. in state 1.
+    pub fn get(&self, x: i64, y: i64) -> bool {
+        let ((cx, lx), (cy, ly)) = (split_coord(x), split_coord(y));
 
-        self.tiles.get(y, x).cloned().unwrap_or(false)
+        self.chunks
+            .get(&(cx, cy))
+            .is_some_and(|chunk| chunk.get(lx, ly))
     }
-    pub fn set_wh(&mut self, w: usize, h: usize) {
-        let mut new_game = Board::new(w, h);
-        let x_offset = (w as isize - self.width() as isize) / 2;
-        let y_offset = (h as isize - self.height() as isize) / 2;
+    pub fn state(&self, x: i64, y: i64) -> u8 {
+        let ((cx, lx), (cy, ly)) = (split_coord(x), split_coord(y));
 
-        for (i, tile) in self.tiles.iter().enumerate() {
-            let (x, y) = self.i_to_xy(i);
+        self.chunks
+            .get(&(cx, cy))
+            .map_or(0, |chunk| chunk.state(lx, ly))
+    }
+    /// Like [`Board::get`], but follows the topology for cells past the edge.
+    pub fn get_resolved(&self, x: i64, y: i64) -> bool {
+        self.topology
+            .resolve(x, y)
+            .is_some_and(|(x, y)| self.get(x, y))
+    }
+    pub fn state_resolved(&self, x: i64, y: i64) -> u8 {
+        self.topology
+            .resolve(x, y)
+            .map_or(0, |(x, y)| self.state(x, y))
+    }
+    pub fn set(&mut self, x: i64, y: i64, value: bool) {
+        self.set_state(x, y, value as u8);
+    }
+    pub fn set_state(&mut self, x: i64, y: i64, state: u8) {
+        let ((cx, lx), (cy, ly)) = (split_coord(x), split_coord(y));
 
-            new_game.try_set(
-                (x as isize + x_offset) as usize,
-                (y as isize + y_offset) as usize,
-                *tile,
-            );
+        if let Some(dirty) = &mut self.dirty {
+            dirty.insert((cx, cy));
         }
 
-        *self = new_game
+        if state != 0 {
+            if !self.topology.contains(x, y) {
+                return;
+            }
+
+            self.chunks
+                .entry((cx, cy))
+                .or_default()
+                .set_state(lx, ly, state);
+        } else if let Some(chunk) = self.chunks.get_mut(&(cx, cy)) {
+            chunk.set_state(lx, ly, 0);
+            if chunk.is_empty() {
+                self.chunks.remove(&(cx, cy));
+            }
+        }
     }
-    pub fn wh(&self) -> (usize, usize) {
-        (self.width(), self.height())
+    /// World coordinates of every live cell, in no particular order.
+    pub fn live_cells(&self) -> impl Iterator<Item = (i64, i64)> + '_ {
+        self.chunks.iter().flat_map(|(&(cx, cy), chunk)| {
+            chunk
+                .cells()
+                .map(move |(x, y)| (cx * CHUNK_SIZE + x as i64, cy * CHUNK_SIZE + y as i64))
+        })
     }
-    pub fn set(&mut self, x: usize, y: usize, value: bool) {
-        if let Some(tile) = self.tiles.get_mut(y, x) {
-            *tile = value;
-        }
+    /// World coordinates and state of every cell that isn't dead.
+    pub fn cells(&self) -> impl Iterator<Item = (i64, i64, u8)> + '_ {
+        self.chunks.iter().flat_map(|(&(cx, cy), chunk)| {
+            chunk.occupied().map(move |(x, y, state)| {
+                (
+                    cx * CHUNK_SIZE + x as i64,
+                    cy * CHUNK_SIZE + y as i64,
+                    state,
+                )
+            })
+        })
     }
-    pub fn try_set(&mut self, x: usize, y: usize, value: bool) -> Option<()> {
-        *self.tiles.get_mut(y, x)? = value;
-        Some(())
+    pub fn population(&self) -> usize {
+        self.chunks.values().map(Chunk::population).sum()
     }
-    fn count_neighbors(&self, x: usize, y: usize) -> u8 {
-        let mut count = 0;
-        for dy in -1..=1 {
-            for dx in -1..=1 {
-                if dx == 0 && dy == 0 {
-                    continue;
-                }
-                let nx = x as i32 + dx;
-                let ny = y as i32 + dy;
-                if nx >= 0 && nx < self.width() as i32 && ny >= 0 && ny < self.height() as i32 {
-                    count += self.get_or_empty(nx as isize, ny as isize) as u8;
-                }
-            }
+    /// The smallest rectangle (inclusive corners) containing every cell that
+    /// isn't dead.
+    pub fn bounds(&self) -> Option<(VecI2, VecI2)> {
+        self.cells().fold(None, |bounds, (x, y, _)| match bounds {
+            None => Some((VecI2::new(x, y), VecI2::new(x, y))),
+            Some((min, max)) => Some((
+                VecI2::new(min.x.min(x), min.y.min(y)),
+                VecI2::new(max.x.max(x), max.y.max(y)),
+            )),
+        })
+    }
+    pub fn set_area(&mut self, pos: VecI2, tiles: &Grid<u8>) {
+        for ((y, x), tile) in tiles.indexed_iter() {
+            self.set_state(pos.x + x as i64, pos.y + y as i64, *tile);
         }
-        count
-    }
-    pub fn set_area(&mut self, pos: VecU2, tiles: &Grid<bool>) {
-        let (dx, dy) = pos.as_tuple();
-        let (w, h) = (tiles.cols(), tiles.rows());
-
-        for x in 0..w {
-            for y in 0..h {
-                self.set(
-                    x + dx,
-                    y + dy,
-                    *tiles.get(y, x).unwrap_or_else(|| {
-                        notify_error("Failed to get tile (set area).");
-                        &false
-                    }),
-                );
-            }
+    }
+    /// Copies the states in the `w` x `h` rectangle whose lowest corner is `pos`.
+    pub fn area(&self, pos: VecI2, w: usize, h: usize) -> Grid<u8> {
+        let mut grid = Grid::new(h, w);
+
+        for ((y, x), tile) in grid.indexed_iter_mut() {
+            *tile = self.state(pos.x + x as i64, pos.y + y as i64);
         }
+
+        grid
     }
-    pub fn i_to_xy(&self, i: usize) -> (usize, usize) {
-        (i % self.width(), i / self.width())
+    /// Every cell, cropped to the bounding box.
+    pub fn to_grid(&self) -> Grid<u8> {
+        match self.bounds() {
+            Some((min, max)) => self.area(
+                min,
+                (max.x - min.x + 1) as usize,
+                (max.y - min.y + 1) as usize,
+            ),
+            None => Grid::new(0, 0),
+        }
     }
     pub fn clear(&mut self) {
-        *self = Self::new(self.width(), self.height());
-    }
-    pub fn crop(&mut self) {
-        for _ in 0..2 {
-            while self.width() > 0 {
-                let mut first_col = self.tiles.iter_col(0);
-                if first_col.all(|v| !v) {
-                    self.tiles.remove_col(0);
-                } else {
-                    break;
+        self.chunks.clear();
+        self.dirty = Some(HashSet::new());
+    }
+    /// Brings each cell of the rectangle between `min` and `max` (inclusive)
+    /// to life with a chance of `density`, leaving masked cells alone.
+    pub fn randomize(
+        &mut self,
+        min: (i64, i64),
+        max: (i64, i64),
+        density: f64,
+        rng: &mut impl Rng,
+    ) {
+        for y in min.1..=max.1 {
+            for x in min.0..=max.0 {
+                if !self.mask.contains_key(&(x, y)) {
+                    self.set(x, y, rng.gen_bool(density.clamp(0., 1.)));
                 }
             }
+        }
+    }
+    /// Swaps in a whole new set of chunks, e.g. from an engine that doesn't
+    /// keep track of which chunks changed.
+    pub fn replace_chunks(&mut self, chunks: ChunkMap) {
+        self.active_chunks = chunks.len();
+        self.chunks = chunks;
+        self.dirty = None;
+    }
+    pub fn print(&self) {
+        print_grid(self.to_grid());
+    }
+}
 
-            while self.height() > 0 {
-                let mut first_row = self.tiles.iter_row(0);
-                if first_row.all(|v| !v) {
-                    self.tiles.remove_row(0);
-                } else {
-                    break;
-                }
-            }
+#[cfg(test)]
+impl Board {
+    /// A random soup filling `topology`, or the plane around the chunk
+    /// boundaries at 0 and 64.
+    pub fn soup(topology: Topology, seed: u64) -> Self {
+        use rand::{rngs::StdRng, SeedableRng};
 
-            self.tiles.rotate_half();
-        }
+        let mut board = Board::new();
+        board.topology = topology;
+        let (min, max) = topology.bounds().unwrap_or(((-40, -40), (100, 100)));
+        board.randomize(min, max, 0.35, &mut StdRng::seed_from_u64(seed));
+        board
     }
-    pub fn draw_line(
-        &mut self,
-        start_x: usize,
-        start_y: usize,
-        end_x: usize,
-        end_y: usize,
-        to: bool,
-    ) {
-        let mut coords: HashSet<(usize, usize)> = HashSet::new();
+    /// Every cell that isn't dead, sorted, for comparing boards.
+    pub fn sorted_cells(&self) -> Vec<(i64, i64, u8)> {
+        let mut cells = self.cells().collect::<Vec<_>>();
+        cells.sort();
+        cells
+    }
+}
 
-        let mut x = start_x as isize;
-        let mut y = start_y as isize;
+/// The cells on a line between two cells, both included.
+pub fn line_cells(start: (i64, i64), end: (i64, i64)) -> Vec<(i64, i64)> {
+    let mut coords = Vec::new();
 
-        let dx = (end_x as isize - start_x as isize).abs();
-        let dy = -(end_y as isize - start_y as isize).abs();
-        let sx = if start_x < end_x { 1 } else { -1 };
-        let sy = if start_y < end_y { 1 } else { -1 };
-        let mut err = dx + dy;
+    let (mut x, mut y) = start;
 
-        loop {
-            coords.insert((x as usize, y as usize));
-            if x == end_x as isize && y == end_y as isize {
+    let dx = (end.0 - start.0).abs();
+    let dy = -(end.1 - start.1).abs();
+    let sx = if start.0 < end.0 { 1 } else { -1 };
+    let sy = if start.1 < end.1 { 1 } else { -1 };
+    let mut err = dx + dy;
+
+    loop {
+        coords.push((x, y));
+        if (x, y) == end {
+            break;
+        }
+        let e2 = 2 * err;
+        if e2 >= dy {
+            if x == end.0 {
                 break;
             }
-            let e2 = 2 * err;
-            if e2 >= dy {
-                if x == end_x as isize {
-                    break;
-                }
-                err += dy;
-                x += sx;
-            }
-            if e2 <= dx {
-                if y == end_y as isize {
-                    break;
-                }
-                err += dx;
-                y += sy;
+            err += dy;
+            x += sx;
+        }
+        if e2 <= dx {
+            if y == end.1 {
+                break;
             }
+            err += dx;
+            y += sy;
         }
+    }
+
+    if coords.last() != Some(&end) {
+        coords.push(end);
+    }
 
-        coords.insert((end_x, end_y));
+    coords
+}
 
-        coords.iter().for_each(|(x, y)| {
-            self.set(*x, *y, to);
-        });
+impl Default for Board {
+    fn default() -> Self {
+        Self::new()
     }
-    pub fn print(&self) {
-        print_grid(self.tiles.clone());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn life() -> Rule {
+        "B3/S23".parse().unwrap()
+    }
+
+    fn chunk_keys(board: &Board) -> Vec<(i64, i64)> {
+        let mut keys: Vec<_> = board.chunks.keys().copied().collect();
+        keys.sort_unstable();
+        keys
+    }
+
+    #[test]
+    fn negative_coordinates_land_in_their_own_chunks() {
+        let mut board = Board::new();
+        board.set(-1, -1, true);
+        board.set(-64, 63, true);
+        board.set_state(-65, 0, 2);
+
+        assert_eq!(chunk_keys(&board), [(-2, 0), (-1, -1), (-1, 0)]);
+        assert!(board.get(-1, -1));
+        assert!(board.get(-64, 63));
+        assert_eq!(board.state(-65, 0), 2);
+        for (x, y) in [(0, 0), (-1, 0), (0, -1), (63, 63), (-63, 63), (-64, -1)] {
+            assert_eq!(board.state(x, y), 0, "({}, {})", x, y);
+        }
+        assert_eq!(
+            board.sorted_cells(),
+            [(-65, 0, 2), (-64, 63, 1), (-1, -1, 1)]
+        );
+    }
+
+    #[test]
+    fn patterns_cross_chunk_borders() {
+        // A glider moving one cell right and down every four generations,
+        // across the edges of chunks in x and y.
+        let glider = [(1, 2), (2, 1), (0, 0), (1, 0), (2, 0)];
+        let mut board = Board::new();
+        for (x, y) in glider {
+            board.set(x + 60, y + 2, true);
+        }
+
+        for _ in 0..32 {
+            board.advance(&life());
+        }
+
+        let mut expected: Vec<_> = glider.iter().map(|&(x, y)| (x + 68, y - 6, 1)).collect();
+        expected.sort_unstable();
+        assert_eq!(board.sorted_cells(), expected);
+        // The chunks it left behind are gone.
+        assert_eq!(chunk_keys(&board), [(1, -1)]);
+
+        // A blinker on the corner of four chunks.
+        let mut board = Board::new();
+        for x in -1..=1 {
+            board.set(x, 0, true);
+        }
+        board.advance(&life());
+        assert_eq!(board.sorted_cells(), [(0, -1, 1), (0, 0, 1), (0, 1, 1)]);
+        board.advance(&life());
+        assert_eq!(board.sorted_cells(), [(-1, 0, 1), (0, 0, 1), (1, 0, 1)]);
+    }
+
+    #[test]
+    fn chunks_come_and_go_with_their_cells() {
+        let mut board = Board::new();
+        board.set(100, 100, true);
+        board.set_state(101, 100, 3);
+        assert_eq!(chunk_keys(&board), [(1, 1)]);
+
+        board.set(100, 100, false);
+        assert_eq!(chunk_keys(&board), [(1, 1)]);
+        board.set_state(101, 100, 0);
+        assert!(board.chunks.is_empty());
+
+        // Clearing dead cells doesn't make chunks.
+        board.set(-500, 3, false);
+        assert!(board.chunks.is_empty());
+
+        // Nor does a pattern dying.
+        board.set(200, 5, true);
+        board.set(-200, 5, true);
+        board.advance(&life());
+        assert!(board.chunks.is_empty());
+        assert_eq!(board.population(), 0);
+    }
+
+    #[test]
+    fn grids_are_cropped_to_the_cells() {
+        let mut board = Board::new();
+        assert_eq!(board.to_grid(), Grid::new(0, 0));
+
+        board.set(-3, 2, true);
+        board.set_state(1, -1, 2);
+        board.set(0, 0, true);
+
+        // Rows go up from the lowest cell.
+        let grid = board.to_grid();
+        assert_eq!((grid.rows(), grid.cols()), (4, 5));
+        assert_eq!(
+            grid,
+            Grid::from_vec(
+                vec![
+                    0, 0, 0, 0, 2, //
+                    0, 0, 0, 1, 0, //
+                    0, 0, 0, 0, 0, //
+                    1, 0, 0, 0, 0, //
+                ],
+                5
+            )
+        );
+
+        let area = board.area(VecI2::new(-1, -1), 3, 2);
+        assert_eq!(area, Grid::from_vec(vec![0, 0, 2, 0, 1, 0], 3));
+
+        // Putting a grid back where it came from gives the same board.
+        let mut copy = Board::new();
+        copy.set_area(VecI2::new(-3, -1), &grid);
+        assert_eq!(copy.sorted_cells(), board.sorted_cells());
     }
 }
@@ -0,0 +1,97 @@
+pub const CHUNK_BITS: u32 = 6;
+pub const CHUNK_SIZE: i64 = 1 << CHUNK_BITS;
+pub const CHUNK_AREA: usize = (CHUNK_SIZE * CHUNK_SIZE) as usize;
+
+/// A `CHUNK_SIZE` x `CHUNK_SIZE` block of cells. Live cells (state 1) are kept
+/// as one bit per cell and one `u64` per row; the full per-cell state is only
+/// allocated once a chunk holds cells in any other state.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Chunk {
+    pub rows: [u64; CHUNK_SIZE as usize],
+    pub states: Option<Box<[u8; CHUNK_AREA]>>,
+}
+
+impl Chunk {
+    pub fn new() -> Self {
+        Self {
+            rows: [0; CHUNK_SIZE as usize],
+            states: None,
+        }
+    }
+    /// Whether the cell is alive, i.e. in state 1.
+    pub fn get(&self, x: usize, y: usize) -> bool {
+        (self.rows[y] >> x) & 1 == 1
+    }
+    pub fn state(&self, x: usize, y: usize) -> u8 {
+        match &self.states {
+            Some(states) => states[y * CHUNK_SIZE as usize + x],
+            None => self.get(x, y) as u8,
+        }
+    }
+    pub fn set_state(&mut self, x: usize, y: usize, state: u8) {
+        if state == 1 {
+            self.rows[y] |= 1 << x;
+        } else {
+            self.rows[y] &= !(1 << x);
+        }
+
+        if state > 1 && self.states.is_none() {
+            let mut states = Box::new([0; CHUNK_AREA]);
+            for (x, y) in self.cells() {
+                states[y * CHUNK_SIZE as usize + x] = 1;
+            }
+            self.states = Some(states);
+        }
+        if let Some(states) = &mut self.states {
+            states[y * CHUNK_SIZE as usize + x] = state;
+        }
+    }
+    pub fn is_empty(&self) -> bool {
+        self.rows.iter().all(|row| *row == 0)
+            && self
+                .states
+                .as_ref()
+                .is_none_or(|states| states.iter().all(|s| *s == 0))
+    }
+    pub fn population(&self) -> usize {
+        self.rows.iter().map(|row| row.count_ones() as usize).sum()
+    }
+    /// Local coordinates of every live cell.
+    pub fn cells(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.rows.iter().enumerate().flat_map(|(y, &row)| {
+            let mut bits = row;
+            std::iter::from_fn(move || {
+                if bits == 0 {
+                    return None;
+                }
+                let x = bits.trailing_zeros() as usize;
+                bits &= bits - 1;
+                Some((x, y))
+            })
+        })
+    }
+    /// Local coordinates and state of every cell that isn't dead.
+    pub fn occupied(&self) -> Box<dyn Iterator<Item = (usize, usize, u8)> + '_> {
+        match &self.states {
+            Some(states) => Box::new(
+                states
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, s)| **s != 0)
+                    .map(|(i, s)| (i % CHUNK_SIZE as usize, i / CHUNK_SIZE as usize, *s)),
+            ),
+            None => Box::new(self.cells().map(|(x, y)| (x, y, 1))),
+        }
+    }
+}
+
+impl Default for Chunk {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Splits a world coordinate into the chunk it belongs to and its offset in it.
+pub fn split_coord(v: i64) -> (i64, usize) {
+    (v >> CHUNK_BITS, (v & (CHUNK_SIZE - 1)) as usize)
+}
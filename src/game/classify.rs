@@ -0,0 +1,277 @@
+use super::{Board, Rule, Swar, Topology};
+use crate::config::CONFIG;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+/// How many times its starting population a soup has to reach to count as
+/// explosive. Its size doesn't count, since gliders spread it out forever.
+const EXPLOSION: usize = 8;
+
+/// How the soups of a rule turn out.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum Behaviour {
+    DiesOut,
+    /// Settles into still lifes and oscillators.
+    Stable,
+    /// Still changing when the run ends, without growing out of control.
+    Chaotic,
+    Explosive,
+}
+
+impl fmt::Display for Behaviour {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Behaviour::DiesOut => "dies out",
+            Behaviour::Stable => "stable",
+            Behaviour::Chaotic => "chaotic",
+            Behaviour::Explosive => "explosive",
+        })
+    }
+}
+
+/// What running a batch of random soups under a rule says about it.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Classification {
+    pub rule: String,
+    /// How most of the soups turned out.
+    pub behaviour: Behaviour,
+    /// Generations soups ran before dying out, settling or exploding, on
+    /// average. Chaotic ones count as running the whole time.
+    pub lifespan: f64,
+    /// Live cells per cell of the square the soups start in, at the end, on
+    /// average.
+    pub density: f64,
+    pub soups: usize,
+    /// How many soups sent objects off out of their area, like gliders.
+    pub gliders: usize,
+}
+
+impl fmt::Display for Classification {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Classification of {}:", self.rule)?;
+        writeln!(f, "   behaviour: {}", self.behaviour)?;
+        writeln!(f, "   average lifespan: {:.0} generations", self.lifespan)?;
+        writeln!(f, "   final density: {:.3}", self.density)?;
+        write!(
+            f,
+            "   glider-like objects: {} of {} soups",
+            self.gliders, self.soups
+        )
+    }
+}
+
+/// The random soups a rule is classified on.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct SoupSettings {
+    pub soups: usize,
+    /// Side of the square of random cells.
+    pub size: u32,
+    /// The chance of each cell starting alive.
+    pub density: f64,
+    /// How long to run each soup for at most.
+    pub generations: u64,
+    /// Seeds the soups, so classifications can be repeated.
+    pub seed: u64,
+}
+
+impl SoupSettings {
+    pub fn from_config(seed: u64) -> Self {
+        Self {
+            soups: CONFIG.classify_soups,
+            size: CONFIG.soup_size,
+            density: CONFIG.soup_density,
+            generations: CONFIG.classify_generations,
+            seed,
+        }
+    }
+}
+
+/// How one soup turned out.
+struct Soup {
+    behaviour: Behaviour,
+    lifespan: u64,
+    density: f64,
+    gliders: bool,
+}
+
+/// Runs `settings.soups` random soups under `rule` in parallel, without
+/// drawing anything, and sums up how they went.
+pub fn classify(rule: &Rule, settings: &SoupSettings) -> Result<Classification, String> {
+    if rule.has_b0() || rule.wolfram().is_some() {
+        return Err(format!(
+            "{}: B0 rules and 1D rules can't be classified on soups",
+            rule.serialize()
+        ));
+    }
+
+    let soups: Vec<Soup> = (0..settings.soups)
+        .into_par_iter()
+        .map(|i| run_soup(rule, settings, settings.seed.wrapping_add(i as u64)))
+        .collect();
+    let count = soups.len().max(1);
+
+    let mut behaviours: HashMap<Behaviour, usize> = HashMap::new();
+    for soup in &soups {
+        *behaviours.entry(soup.behaviour).or_default() += 1;
+    }
+    // Ties go to the livelier behaviour.
+    let behaviour = behaviours
+        .into_iter()
+        .max_by_key(|&(behaviour, soups)| (soups, behaviour))
+        .map_or(Behaviour::DiesOut, |(behaviour, _)| behaviour);
+
+    Ok(Classification {
+        rule: rule.serialize(),
+        behaviour,
+        lifespan: soups.iter().map(|soup| soup.lifespan as f64).sum::<f64>() / count as f64,
+        density: soups.iter().map(|soup| soup.density).sum::<f64>() / count as f64,
+        soups: soups.len(),
+        gliders: soups.iter().filter(|soup| soup.gliders).count(),
+    })
+}
+
+fn run_soup(rule: &Rule, settings: &SoupSettings, seed: u64) -> Soup {
+    let mut rng = StdRng::seed_from_u64(seed);
+    // Soups are checked every generation, so they can't use Hashlife's big
+    // steps. Swar hands rules it can't run to the fallback engine itself.
+    let mut board = Board::with_engine(Swar);
+    board.topology = rule.topology.unwrap_or(Topology::Plane);
+    board.seed = rng.gen();
+
+    let size = settings.size.max(1) as i64;
+    let half = size / 2;
+    let (min, max) = ((-half, -half), (size - half - 1, size - half - 1));
+    board.randomize(min, max, settings.density, &mut rng);
+    let initial = board.population().max(1);
+
+    // Objects that get this far from the soup have left it for good. The
+    // core is compared between generations to spot the soup settling,
+    // which escaping objects would hide.
+    let reach = half + size;
+    let in_core = |x: i64, y: i64| x.abs() <= reach && y.abs() <= reach;
+    let mut seen: HashMap<u64, u64> = HashMap::new();
+
+    let (behaviour, lifespan) = loop {
+        if board.generation >= settings.generations {
+            break (Behaviour::Chaotic, settings.generations);
+        }
+        board.advance(rule);
+
+        let population = board.population();
+        if population == 0 {
+            break (Behaviour::DiesOut, board.generation);
+        }
+        if population > EXPLOSION * initial {
+            break (Behaviour::Explosive, board.generation);
+        }
+
+        // Order-independent, since chunks come out in any order.
+        let core = board
+            .cells()
+            .filter(|&(x, y, _)| in_core(x, y))
+            .fold(0u64, |sum, cell| {
+                let mut hasher = DefaultHasher::new();
+                cell.hash(&mut hasher);
+                sum.wrapping_add(hasher.finish())
+            });
+        if let Some(&settled) = seen.get(&core) {
+            break (Behaviour::Stable, settled);
+        }
+        seen.insert(core, board.generation);
+    };
+
+    let in_soup = |x: i64, y: i64| (min.0..=max.0).contains(&x) && (min.1..=max.1).contains(&y);
+    let density =
+        board.live_cells().filter(|&(x, y)| in_soup(x, y)).count() as f64 / (size * size) as f64;
+    let gliders =
+        behaviour != Behaviour::Explosive && board.cells().any(|(x, y, _)| !in_core(x, y));
+
+    Soup {
+        behaviour,
+        lifespan,
+        density,
+        gliders,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SETTINGS: SoupSettings = SoupSettings {
+        soups: 8,
+        size: 32,
+        density: 0.35,
+        generations: 500,
+        seed: 1,
+    };
+
+    fn classified(rule: &str) -> Classification {
+        classify(&rule.parse().unwrap(), &SETTINGS).unwrap()
+    }
+
+    #[test]
+    fn life_settles_or_keeps_going() {
+        let life = classified("B3/S23");
+        assert!(matches!(
+            life.behaviour,
+            Behaviour::Stable | Behaviour::Chaotic
+        ));
+        assert_eq!(life.soups, 8);
+        assert!(life.density > 0.);
+        // The same seed gives the same soups.
+        assert_eq!(classified("B3/S23"), life);
+    }
+
+    #[test]
+    fn rules_without_survival_or_births_die_out() {
+        let classification = classified("B/S");
+        assert_eq!(classification.behaviour, Behaviour::DiesOut);
+        assert_eq!(classification.lifespan, 1.);
+        assert_eq!(classification.density, 0.);
+        assert_eq!(classification.gliders, 0);
+    }
+
+    #[test]
+    fn spaceships_are_spotted() {
+        // HighLife's soups send out gliders, B3/S013's only leave still lifes
+        // and B2/S's spread too fast to tell.
+        assert!(classified("B36/S23").gliders > 0);
+
+        let still = classified("B3/S013");
+        assert_eq!(still.behaviour, Behaviour::Stable);
+        assert_eq!(still.gliders, 0);
+
+        let seeds = classified("B2/S");
+        assert_eq!(seeds.behaviour, Behaviour::Explosive);
+        assert_eq!(seeds.gliders, 0);
+    }
+
+    #[test]
+    fn b0_and_1d_rules_are_skipped() {
+        for rule in ["B0/S8", "W30"] {
+            assert!(classify(&rule.parse().unwrap(), &SETTINGS).is_err());
+        }
+    }
+
+    #[test]
+    fn classifications_export_as_json() {
+        let classification = classified("B/S");
+        let line = serde_json::to_string(&classification).unwrap();
+
+        assert!(!line.contains('\n'));
+        assert_eq!(
+            line,
+            r#"{"rule":"B/S","behaviour":"dies_out","lifespan":1.0,"density":0.0,"soups":8,"gliders":0}"#
+        );
+        assert_eq!(
+            serde_json::from_str::<Classification>(&line).unwrap(),
+            classification
+        );
+    }
+}
@@ -0,0 +1,152 @@
+use serde::{Deserialize, Serialize};
+
+/// A continuous automaton, where cells hold values from 0 to 1 and move
+/// towards whatever a growth function makes of weighted averages of the
+/// cells around them.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ContinuousRule {
+    /// Bert Chan's Lenia: one kernel of concentric rings, `peaks` giving the
+    /// height of each, and a Gaussian growth function centred on `mu`.
+    Lenia {
+        radius: f32,
+        peaks: Vec<f32>,
+        mu: f32,
+        sigma: f32,
+        dt: f32,
+    },
+    /// Stephan Rafler's SmoothLife: cells look at the filling of a disk a
+    /// third of `outer_radius` wide and of the ring around it, and are born
+    /// or survive when the ring's filling is within `birth` or `death`. A
+    /// `dt` of 1 replaces every cell each generation, smaller ones blend.
+    SmoothLife {
+        outer_radius: f32,
+        birth: [f32; 2],
+        death: [f32; 2],
+        alpha_n: f32,
+        alpha_m: f32,
+        dt: f32,
+    },
+}
+
+/// A continuous rule with the name `continuous` picks it by.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct ContinuousPreset {
+    pub name: String,
+    pub rule: ContinuousRule,
+}
+
+impl ContinuousRule {
+    /// How far the kernels reach.
+    pub fn radius(&self) -> f32 {
+        match self {
+            ContinuousRule::Lenia { radius, .. } => *radius,
+            ContinuousRule::SmoothLife { outer_radius, .. } => *outer_radius,
+        }
+    }
+
+    pub fn kernel_count(&self) -> usize {
+        match self {
+            ContinuousRule::Lenia { .. } => 1,
+            ContinuousRule::SmoothLife { .. } => 2,
+        }
+    }
+
+    /// The weight of a cell `distance` away in kernel `index`, before the
+    /// kernel is normalised to sum to 1.
+    pub fn kernel(&self, index: usize, distance: f32) -> f32 {
+        match self {
+            ContinuousRule::Lenia { radius, peaks, .. } => {
+                let r = distance / radius;
+                if r >= 1. || peaks.is_empty() {
+                    return 0.;
+                }
+                let ring = r * peaks.len() as f32;
+                peaks[ring as usize] * bump(ring.fract())
+            }
+            ContinuousRule::SmoothLife { outer_radius, .. } => {
+                // Anti-aliased edges, so the fillings change smoothly.
+                let disk = |radius: f32| (radius + 0.5 - distance).clamp(0., 1.);
+                let inner = disk(outer_radius / 3.);
+                match index {
+                    0 => inner,
+                    _ => disk(*outer_radius) - inner,
+                }
+            }
+        }
+    }
+
+    /// The next value of a cell, given the weighted average of the field
+    /// under each kernel around it.
+    pub fn next(&self, value: f32, potential: impl Fn(usize) -> f32) -> f32 {
+        let next = match self {
+            ContinuousRule::Lenia { mu, sigma, dt, .. } => {
+                let u = potential(0);
+                let growth = 2. * (-(u - mu).powi(2) / (2. * sigma * sigma)).exp() - 1.;
+                value + dt * growth
+            }
+            ContinuousRule::SmoothLife {
+                birth,
+                death,
+                alpha_n,
+                alpha_m,
+                dt,
+                ..
+            } => {
+                let (m, n) = (potential(0), potential(1));
+                let sigmoid =
+                    |x: f32, a: f32, alpha: f32| 1. / (1. + (-(x - a) * 4. / alpha).exp());
+                let alive = sigmoid(m, 0.5, *alpha_m);
+                let mix = |born: f32, survive: f32| born * (1. - alive) + survive * alive;
+                let (low, high) = (mix(birth[0], death[0]), mix(birth[1], death[1]));
+                let s = sigmoid(n, low, *alpha_n) * (1. - sigmoid(n, high, *alpha_n));
+                value + dt * (s - value)
+            }
+        };
+        next.clamp(0., 1.)
+    }
+
+    pub fn presets() -> Vec<ContinuousPreset> {
+        vec![
+            ContinuousPreset {
+                name: String::from("Orbium"),
+                rule: ContinuousRule::Lenia {
+                    radius: 13.,
+                    peaks: vec![1.],
+                    mu: 0.15,
+                    sigma: 0.015,
+                    dt: 0.1,
+                },
+            },
+            ContinuousPreset {
+                name: String::from("Hydrogeminium"),
+                rule: ContinuousRule::Lenia {
+                    radius: 18.,
+                    peaks: vec![0.5, 1., 0.667],
+                    mu: 0.26,
+                    sigma: 0.036,
+                    dt: 0.1,
+                },
+            },
+            ContinuousPreset {
+                name: String::from("SmoothLife"),
+                rule: ContinuousRule::SmoothLife {
+                    outer_radius: 21.,
+                    birth: [0.278, 0.365],
+                    death: [0.267, 0.445],
+                    alpha_n: 0.028,
+                    alpha_m: 0.147,
+                    dt: 0.2,
+                },
+            },
+        ]
+    }
+}
+
+/// Lenia's exponential bump, rising from 0 to 1 and back over `0..1`.
+fn bump(x: f32) -> f32 {
+    if x <= 0. || x >= 1. {
+        return 0.;
+    }
+    (4. - 1. / (x * (1. - x))).exp()
+}
@@ -0,0 +1,400 @@
+use super::Engine;
+use crate::game::{split_coord, Board, ChunkMap, Rule, Topology};
+use std::collections::HashMap;
+
+type NodeId = u32;
+
+const DEAD: NodeId = 0;
+const ALIVE: NodeId = 1;
+
+/// Past this many nodes the whole cache is thrown away. Steps that run past
+/// it halfway through start again from an empty cache.
+const MAX_NODES: usize = 1 << 22;
+
+/// A square of `2^level` cells. Level 0 nodes are single cells; every other
+/// node is made of four children one level down.
+#[derive(Clone, Copy, Debug)]
+struct Node {
+    nw: NodeId,
+    ne: NodeId,
+    sw: NodeId,
+    se: NodeId,
+    level: u8,
+    population: u64,
+}
+
+/// Gosper's Hashlife: the board is stored as a hash-consed quadtree and the
+/// result of evolving every node is memoized, so repetitive patterns can be
+/// pushed forward `2^step` generations at once.
+#[derive(Debug)]
+pub struct Hashlife {
+    step: u32,
+    /// The step the memoized results are for, which is smaller than `step`
+    /// while patterns are too big to jump that far within `MAX_NODES`.
+    jump: u32,
+    rule: Option<Rule>,
+    nodes: Vec<Node>,
+    lookup: HashMap<[NodeId; 4], NodeId>,
+    results: HashMap<NodeId, NodeId>,
+    empty: Vec<NodeId>,
+}
+
+impl Hashlife {
+    pub fn new(step: u32) -> Self {
+        let step = step.min(48);
+        let mut hashlife = Self {
+            step,
+            jump: step,
+            rule: None,
+            nodes: Vec::new(),
+            lookup: HashMap::new(),
+            results: HashMap::new(),
+            empty: Vec::new(),
+        };
+        hashlife.reset();
+
+        hashlife
+    }
+    fn reset(&mut self) {
+        let leaf = |population| Node {
+            nw: DEAD,
+            ne: DEAD,
+            sw: DEAD,
+            se: DEAD,
+            level: 0,
+            population,
+        };
+
+        self.nodes = vec![leaf(0), leaf(1)];
+        self.lookup.clear();
+        self.results.clear();
+        self.empty = vec![DEAD];
+    }
+    fn join(&mut self, nw: NodeId, ne: NodeId, sw: NodeId, se: NodeId) -> NodeId {
+        let key = [nw, ne, sw, se];
+        if let Some(&id) = self.lookup.get(&key) {
+            return id;
+        }
+
+        let nodes = &self.nodes;
+        let node = Node {
+            nw,
+            ne,
+            sw,
+            se,
+            level: nodes[nw as usize].level + 1,
+            population: key.iter().map(|&c| nodes[c as usize].population).sum(),
+        };
+        let id = self.nodes.len() as NodeId;
+        self.nodes.push(node);
+        self.lookup.insert(key, id);
+
+        id
+    }
+    fn empty(&mut self, level: u8) -> NodeId {
+        while self.empty.len() <= level as usize {
+            let e = *self.empty.last().unwrap();
+            let next = self.join(e, e, e, e);
+            self.empty.push(next);
+        }
+
+        self.empty[level as usize]
+    }
+    fn node(&self, id: NodeId) -> Node {
+        self.nodes[id as usize]
+    }
+    /// Wraps `id` in a node one level up, keeping it in the middle.
+    fn centre(&mut self, id: NodeId) -> NodeId {
+        let n = self.node(id);
+        let e = self.empty(n.level - 1);
+
+        let nw = self.join(e, e, e, n.nw);
+        let ne = self.join(e, e, n.ne, e);
+        let sw = self.join(e, n.sw, e, e);
+        let se = self.join(n.se, e, e, e);
+
+        self.join(nw, ne, sw, se)
+    }
+    /// Whether all live cells of `id` are inside its central quarter.
+    fn is_padded(&self, id: NodeId) -> bool {
+        let n = self.node(id);
+        let (nw, ne, sw, se) = (
+            self.node(n.nw),
+            self.node(n.ne),
+            self.node(n.sw),
+            self.node(n.se),
+        );
+
+        nw.population == self.node(self.node(nw.se).se).population
+            && ne.population == self.node(self.node(ne.sw).sw).population
+            && sw.population == self.node(self.node(sw.ne).ne).population
+            && se.population == self.node(self.node(se.nw).nw).population
+    }
+    /// Builds a node of the given level from cells relative to its corner.
+    fn build(&mut self, cells: &[(i64, i64)], level: u8) -> NodeId {
+        if cells.is_empty() {
+            return self.empty(level);
+        }
+        if level == 0 {
+            return ALIVE;
+        }
+
+        let half = 1 << (level - 1);
+        let mut quadrants: [Vec<(i64, i64)>; 4] = Default::default();
+
+        for &(x, y) in cells {
+            let i = (x >= half) as usize + 2 * (y >= half) as usize;
+            quadrants[i].push((x % half, y % half));
+        }
+
+        let [nw, ne, sw, se] = quadrants.map(|q| self.build(&q, level - 1));
+
+        self.join(nw, ne, sw, se)
+    }
+    fn collect(&self, id: NodeId, x: i64, y: i64, out: &mut Vec<(i64, i64)>) {
+        let n = self.node(id);
+
+        if n.population == 0 {
+            return;
+        }
+        if n.level == 0 {
+            out.push((x, y));
+            return;
+        }
+
+        let half = 1 << (n.level - 1);
+        self.collect(n.nw, x, y, out);
+        self.collect(n.ne, x + half, y, out);
+        self.collect(n.sw, x, y + half, out);
+        self.collect(n.se, x + half, y + half, out);
+    }
+    /// Runs the rule directly on a 4x4 node, giving its central 2x2 one
+    /// generation later.
+    fn step_leaf(&mut self, id: NodeId, rule: &Rule) -> NodeId {
+        let rule = rule
+            .life_like()
+            .expect("only life-like rules are supported");
+        let n = self.node(id);
+        let mut cells = [[false; 4]; 4];
+
+        for (i, quadrant) in [n.nw, n.ne, n.sw, n.se].into_iter().enumerate() {
+            let q = self.node(quadrant);
+            let (ox, oy) = (2 * (i % 2), 2 * (i / 2));
+
+            for (j, cell) in [q.nw, q.ne, q.sw, q.se].into_iter().enumerate() {
+                cells[oy + j / 2][ox + j % 2] = cell == ALIVE;
+            }
+        }
+
+        let mut next = [DEAD; 4];
+        for (i, cell) in next.iter_mut().enumerate() {
+            let (x, y) = (1 + i % 2, 1 + i / 2);
+            let mut neighbours = 0;
+            for dy in [2, 1, 0] {
+                for dx in 0..3 {
+                    if (dx, dy) != (1, 1) {
+                        neighbours = (neighbours << 1) | cells[y + dy - 1][x + dx - 1] as u8;
+                    }
+                }
+            }
+
+            if rule.next_state(cells[y][x] as u8, neighbours) == 1 {
+                *cell = ALIVE;
+            }
+        }
+
+        self.join(next[0], next[1], next[2], next[3])
+    }
+    /// The central half of `id`, `2^min(jump, level - 2)` generations later,
+    /// or `None` if the cache fills up on the way. Single generations always
+    /// finish, so that steps can't be put off forever.
+    fn successor(&mut self, id: NodeId, rule: &Rule) -> Option<NodeId> {
+        let n = self.node(id);
+
+        if n.population == 0 {
+            return Some(self.empty(n.level - 1));
+        }
+        if let Some(&result) = self.results.get(&id) {
+            return Some(result);
+        }
+        if self.jump > 0 && self.nodes.len() > MAX_NODES {
+            return None;
+        }
+
+        let result = if n.level == 2 {
+            self.step_leaf(id, rule)
+        } else {
+            let (nw, ne, sw, se) = (
+                self.node(n.nw),
+                self.node(n.ne),
+                self.node(n.sw),
+                self.node(n.se),
+            );
+
+            let n00 = n.nw;
+            let n01 = self.join(nw.ne, ne.nw, nw.se, ne.sw);
+            let n02 = n.ne;
+            let n10 = self.join(nw.sw, nw.se, sw.nw, sw.ne);
+            let n11 = self.join(nw.se, ne.sw, sw.ne, se.nw);
+            let n12 = self.join(ne.sw, ne.se, se.nw, se.ne);
+            let n20 = n.sw;
+            let n21 = self.join(sw.ne, se.nw, sw.se, se.sw);
+            let n22 = n.se;
+
+            let s00 = self.successor(n00, rule)?;
+            let s01 = self.successor(n01, rule)?;
+            let s02 = self.successor(n02, rule)?;
+            let s10 = self.successor(n10, rule)?;
+            let s11 = self.successor(n11, rule)?;
+            let s12 = self.successor(n12, rule)?;
+            let s20 = self.successor(n20, rule)?;
+            let s21 = self.successor(n21, rule)?;
+            let s22 = self.successor(n22, rule)?;
+
+            if self.jump + 2 < n.level as u32 {
+                // Stepping any further at this level would overshoot, so just
+                // stitch together the centres of the nine intermediate nodes.
+                let [s00, s01, s02, s10, s11, s12, s20, s21, s22] =
+                    [s00, s01, s02, s10, s11, s12, s20, s21, s22].map(|id| self.node(id));
+
+                let nw = self.join(s00.se, s01.sw, s10.ne, s11.nw);
+                let ne = self.join(s01.se, s02.sw, s11.ne, s12.nw);
+                let sw = self.join(s10.se, s11.sw, s20.ne, s21.nw);
+                let se = self.join(s11.se, s12.sw, s21.ne, s22.nw);
+
+                self.join(nw, ne, sw, se)
+            } else {
+                let nw = self.join(s00, s01, s10, s11);
+                let ne = self.join(s01, s02, s11, s12);
+                let sw = self.join(s10, s11, s20, s21);
+                let se = self.join(s11, s12, s21, s22);
+
+                let nw = self.successor(nw, rule)?;
+                let ne = self.successor(ne, rule)?;
+                let sw = self.successor(sw, rule)?;
+                let se = self.successor(se, rule)?;
+
+                self.join(nw, ne, sw, se)
+            }
+        };
+
+        self.results.insert(id, result);
+
+        Some(result)
+    }
+}
+
+impl Hashlife {
+    /// The live cells of `board` after `2^jump` generations, or `None` if the
+    /// cache fills up on the way.
+    fn jump_board(&mut self, board: &Board, rule: &Rule) -> Option<ChunkMap> {
+        let Some((min, max)) = board.bounds() else {
+            return Some(ChunkMap::new());
+        };
+
+        let cells = board
+            .live_cells()
+            .map(|(x, y)| (x - min.x, y - min.y))
+            .collect::<Vec<_>>();
+
+        let side = (max.x - min.x).max(max.y - min.y) + 1;
+        let mut level = (64 - (side - 1).leading_zeros() as u8).max(1);
+        let mut root = self.build(&cells, level);
+        let mut origin = (min.x, min.y);
+
+        while level < 3 || (level as u32) < self.jump + 3 || !self.is_padded(root) {
+            root = self.centre(root);
+            origin.0 -= 1 << (level - 1);
+            origin.1 -= 1 << (level - 1);
+            level += 1;
+        }
+
+        let next = self.successor(root, rule)?;
+        origin.0 += 1 << (level - 2);
+        origin.1 += 1 << (level - 2);
+
+        let mut out = Vec::new();
+        self.collect(next, origin.0, origin.1, &mut out);
+
+        // Built straight from the cells rather than through `Board::new`,
+        // which would look at the config.
+        let mut chunks = ChunkMap::new();
+        for (x, y) in out {
+            let ((cx, lx), (cy, ly)) = (split_coord(x), split_coord(y));
+            chunks.entry((cx, cy)).or_default().set_state(lx, ly, 1);
+        }
+
+        Some(chunks)
+    }
+}
+
+impl Engine for Hashlife {
+    fn advance(&mut self, board: &mut Board, rule: &Rule) -> u64 {
+        if self.rule.as_ref() != Some(rule) || self.nodes.len() > MAX_NODES {
+            self.reset();
+            self.rule = Some(rule.clone());
+        }
+
+        // A step that fills the cache starts again from an empty one, and
+        // jumps half as far if it filled an empty one.
+        let mut jump = self.step;
+        loop {
+            if jump != self.jump {
+                self.reset();
+                self.jump = jump;
+            }
+            let fresh = self.results.is_empty();
+
+            match self.jump_board(board, rule) {
+                Some(chunks) => {
+                    board.replace_chunks(chunks);
+                    return 1 << jump;
+                }
+                None if fresh => jump -= 1,
+                None => self.reset(),
+            }
+        }
+    }
+    fn supports(&self, rule: &Rule, topology: Topology) -> bool {
+        rule.states() == 2
+            && rule.table().is_some()
+            && !rule.has_b0()
+            && topology == Topology::Plane
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Parallel;
+
+    #[test]
+    fn jumps_match_single_generations() {
+        let rule: Rule = "B3/S23".parse().unwrap();
+        // An R-pentomino, which takes over a thousand generations to settle,
+        // and a glider.
+        let r_pentomino = [(1, 0), (2, 0), (0, 1), (1, 1), (1, 2)];
+        let glider = [(20, 20), (21, 21), (19, 22), (20, 22), (21, 22)];
+
+        for step in [0, 3, 6] {
+            let mut jumped = Board::new();
+            let mut stepped = Board::new();
+            for &(x, y) in r_pentomino.iter().chain(&glider) {
+                jumped.set(x, y, true);
+                stepped.set(x, y, true);
+            }
+
+            let generations = Hashlife::new(step).advance(&mut jumped, &rule);
+            for _ in 0..generations {
+                Parallel.advance(&mut stepped, &rule);
+            }
+
+            let mut jumped = jumped.live_cells().collect::<Vec<_>>();
+            let mut stepped = stepped.live_cells().collect::<Vec<_>>();
+            jumped.sort();
+            stepped.sort();
+            assert_eq!(generations, 1 << step);
+            assert_eq!(jumped, stepped, "step {}", step);
+        }
+    }
+}
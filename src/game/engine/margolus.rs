@@ -0,0 +1,99 @@
+use super::{active_chunks, apply_chunks, parallel::Padded, Engine};
+use crate::game::{BlockRule, Board, Chunk, Rule, Topology, CHUNK_SIZE};
+use rayon::prelude::*;
+
+const SIZE: usize = CHUNK_SIZE as usize;
+
+/// Steps block rules, which replace every 2x2 block of the partition at once
+/// instead of looking at cells one by one.
+#[derive(Debug)]
+pub struct Margolus;
+
+impl Engine for Margolus {
+    fn advance(&mut self, board: &mut Board, rule: &Rule) -> u64 {
+        let blocks = rule.blocks().expect("only block rules are supported");
+        step_blocks(board, blocks, board.generation % 2 == 1);
+
+        1
+    }
+
+    fn supports(&self, rule: &Rule, _topology: Topology) -> bool {
+        rule.blocks().is_some()
+    }
+}
+
+/// Replaces every block through `blocks`. Even generations use the blocks
+/// whose lower left cell has even coordinates and odd ones are offset by one
+/// cell diagonally, so both chunk edges and the seams of even sized tori
+/// line up with the partition.
+pub fn step_blocks(board: &mut Board, blocks: &BlockRule, odd: bool) {
+    // Chunks that stood still under one partition may not under the other.
+    board.dirty = None;
+
+    let next = active_chunks(board, 1, blocks.strobes())
+        .into_par_iter()
+        .map(|key| (key, step_chunk(board, key, blocks, odd)))
+        .collect::<Vec<_>>();
+
+    apply_chunks(board, next);
+}
+
+fn step_chunk(board: &Board, (cx, cy): (i64, i64), blocks: &BlockRule, odd: bool) -> Chunk {
+    let (x0, y0) = (cx * CHUNK_SIZE, cy * CHUNK_SIZE);
+    let topology = board.topology;
+    let interior =
+        topology.contains(x0 - 1, y0 - 1) && topology.contains(x0 + CHUNK_SIZE, y0 + CHUNK_SIZE);
+    let padded = Padded::gather(board, (cx, cy), 1, interior);
+
+    let mut next = Chunk::new();
+    for y in 0..SIZE {
+        for x in 0..SIZE {
+            if !interior && !topology.contains(x0 + x as i64, y0 + y as i64) {
+                continue;
+            }
+
+            // Where the cell sits in its block, and the block's lower left
+            // cell in `padded`.
+            let (dx, dy) = ((x + odd as usize) % 2, (y + odd as usize) % 2);
+            let (bx, by) = (x + 1 - dx, y + 1 - dy);
+            let block = padded.get(bx, by + 1) as u8
+                | (padded.get(bx + 1, by + 1) as u8) << 1
+                | (padded.get(bx, by) as u8) << 2
+                | (padded.get(bx + 1, by) as u8) << 3;
+
+            let bit = dx + 2 * (1 - dy);
+            if (blocks.next(block) >> bit) & 1 == 1 {
+                next.set_state(x, y, 1);
+            }
+        }
+    }
+
+    next
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game::{Board, Rule, Topology};
+
+    #[test]
+    fn steps_back_to_where_it_started() {
+        for rulestring in ["Critters", "BBM"] {
+            let rule: Rule = rulestring.parse().unwrap();
+            let mut board = Board::soup(Topology::Plane, 3);
+            let start = board.sorted_cells();
+
+            for _ in 0..25 {
+                board.advance(&rule);
+            }
+            assert_eq!(board.generation, 25);
+            assert_ne!(board.sorted_cells(), start, "{}", rulestring);
+            for _ in 0..25 {
+                assert!(board.step_back(&rule), "{}", rulestring);
+            }
+
+            assert_eq!(board.generation, 0);
+            assert_eq!(board.sorted_cells(), start, "{}", rulestring);
+            assert!(!board.step_back(&rule));
+        }
+    }
+}
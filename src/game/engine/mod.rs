@@ -0,0 +1,219 @@
+pub use hashlife::*;
+pub use margolus::*;
+pub use parallel::*;
+pub use sequential::*;
+pub use space_time::*;
+pub use swar::*;
+
+use crate::{
+    config::CONFIG,
+    game::{split_coord, Board, Chunk, Rule, Topology, CHUNK_SIZE},
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashSet,
+    fmt,
+    sync::{Arc, Mutex},
+};
+
+mod hashlife;
+mod margolus;
+mod parallel;
+mod sequential;
+mod space_time;
+mod swar;
+
+/// Something that knows how to compute the next generation(s) of a [`Board`].
+pub trait Engine: fmt::Debug + Send {
+    /// Advances the board, returning how many generations were simulated.
+    fn advance(&mut self, board: &mut Board, rule: &Rule) -> u64;
+    /// Whether this engine can simulate the rule on the given topology.
+    /// Boards fall back to [`fallback`] when it can't, and when they have
+    /// rule regions or masked cells, which turns Hashlife and SWAR off.
+    fn supports(&self, _rule: &Rule, _topology: Topology) -> bool {
+        true
+    }
+}
+
+/// The engine for rules the configured one can't simulate: [`Margolus`] for
+/// block rules, [`SpaceTime`] for 1D rules and [`Parallel`] for the rest,
+/// which also runs boards with rule regions.
+pub fn fallback(rule: &Rule) -> Box<dyn Engine> {
+    if rule.blocks().is_some() {
+        Box::new(Margolus)
+    } else if rule.wolfram().is_some() {
+        Box::new(SpaceTime)
+    } else {
+        Box::new(Parallel)
+    }
+}
+
+pub type SharedEngine = Arc<Mutex<dyn Engine>>;
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum EngineKind {
+    /// Cell-by-cell simulation; uses rayon when `parallel_board_processing` is set.
+    Classic,
+    /// Bit-packed simulation of 64 cells per word, spread over the rayon pool.
+    Swar,
+    /// Quadtree simulation that jumps `2^hashlife_step` generations per update.
+    Hashlife,
+}
+
+impl EngineKind {
+    pub fn build(self) -> SharedEngine {
+        match self {
+            EngineKind::Classic if CONFIG.parallel_board_processing => {
+                Arc::new(Mutex::new(Parallel))
+            }
+            EngineKind::Classic => Arc::new(Mutex::new(Sequential)),
+            EngineKind::Swar => Arc::new(Mutex::new(Swar)),
+            EngineKind::Hashlife => Arc::new(Mutex::new(Hashlife::new(CONFIG.hashlife_step))),
+        }
+    }
+}
+
+/// Chunks whose next generation may differ from the current one: those next
+/// to a chunk that just changed, plus the edges of bounded topologies, which
+/// can see the far side. `range` is how far cells can see, at most a chunk.
+/// `b0` is whether empty space comes alive, which on bounded planes, where
+/// B0 rules aren't emulated, can happen in any chunk.
+fn active_chunks(board: &Board, range: i64, b0: bool) -> HashSet<(i64, i64)> {
+    let neighbours = |&(cx, cy): &(i64, i64)| {
+        (-1..=1).flat_map(move |dy| (-1..=1).map(move |dx| (cx + dx, cy + dy)))
+    };
+
+    let mut keys = match &board.dirty {
+        Some(dirty) => dirty.iter().flat_map(neighbours).collect::<HashSet<_>>(),
+        None => board.chunks.keys().flat_map(neighbours).collect(),
+    };
+
+    if let Some(((x0, y0), (x1, y1))) = board.topology.bounds() {
+        let (min_cx, max_cx) = (split_coord(x0).0, split_coord(x1).0);
+        let (min_cy, max_cy) = (split_coord(y0).0, split_coord(y1).0);
+
+        if b0 {
+            return (min_cx..=max_cx)
+                .flat_map(|cx| (min_cy..=max_cy).map(move |cy| (cx, cy)))
+                .collect();
+        }
+
+        // Chunks holding cells within `range` of an edge.
+        let near = |c: i64, min: i64, max: i64| {
+            c * CHUNK_SIZE < min + range || (c + 1) * CHUNK_SIZE - 1 > max - range
+        };
+        let edge_columns = (min_cx..=max_cx)
+            .filter(|&cx| near(cx, x0, x1))
+            .collect::<Vec<_>>();
+        let edge_rows = (min_cy..=max_cy)
+            .filter(|&cy| near(cy, y0, y1))
+            .collect::<Vec<_>>();
+
+        keys.retain(|&(cx, cy)| (min_cx..=max_cx).contains(&cx) && (min_cy..=max_cy).contains(&cy));
+
+        if keys
+            .iter()
+            .any(|&(cx, cy)| near(cx, x0, x1) || near(cy, y0, y1))
+        {
+            for cx in min_cx..=max_cx {
+                keys.extend(edge_rows.iter().map(|&cy| (cx, cy)));
+            }
+            for cy in min_cy..=max_cy {
+                keys.extend(edge_columns.iter().map(|&cx| (cx, cy)));
+            }
+        }
+    }
+
+    keys
+}
+
+/// Writes recomputed chunks back into the board and remembers which of them
+/// actually changed, so the next generation can skip the rest.
+fn apply_chunks(board: &mut Board, next: Vec<((i64, i64), Chunk)>) {
+    let mut changed = HashSet::new();
+    board.active_chunks = next.len();
+
+    for (key, chunk) in next {
+        if board.chunks.get(&key).unwrap_or(&Chunk::new()) == &chunk {
+            continue;
+        }
+
+        changed.insert(key);
+        if chunk.is_empty() {
+            board.chunks.remove(&key);
+        } else {
+            board.chunks.insert(key, chunk);
+        }
+    }
+
+    board.dirty = Some(changed);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    /// Runs a few rules in turn on soups across chunk edges and next to the
+    /// edges of the topology, checking each generation against a board that
+    /// doesn't skip chunks but recomputes every cell, one at a time.
+    fn assert_skipping_matches(engine: fn() -> Board, topology: Topology) {
+        let rules: Vec<Rule> = ["B3/S23", "B36/S23", "B03/S23", "B2/S", "B3/S23"]
+            .iter()
+            .map(|rulestring| rulestring.parse().unwrap())
+            .collect();
+
+        let mut skipping = engine();
+        skipping.topology = topology;
+        let mut rng = StdRng::seed_from_u64(2);
+        skipping.randomize((-20, -20), (19, 19), 0.4, &mut rng);
+        skipping.randomize((40, 20), (74, 49), 0.4, &mut rng);
+        let mut full = Board::with_engine(Sequential);
+        full.topology = topology;
+        for (x, y, state) in skipping.cells() {
+            full.set_state(x, y, state);
+        }
+
+        let mut current = &rules[0];
+        for rule in &rules {
+            for board in [&mut skipping, &mut full] {
+                board.change_rule(current, rule).unwrap();
+            }
+            current = rule;
+
+            // An even number of generations, so B0 rules on the plane end
+            // on a dead background and can be left.
+            for _ in 0..24 {
+                skipping.advance(rule);
+                full.advance(rule);
+                assert_eq!(
+                    skipping.sorted_cells(),
+                    full.sorted_cells(),
+                    "{} on {} at generation {}",
+                    rule.serialize(),
+                    topology.serialize(),
+                    skipping.generation
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn skipping_chunks_matches_recomputing_them_all() {
+        let topologies = [
+            "",
+            "T150,100",
+            "K150*,100",
+            "C120,120",
+            "P150,100",
+            "P400,300",
+            "T130,70",
+        ]
+        .map(|text| text.parse::<Topology>().unwrap());
+        for topology in topologies {
+            assert_skipping_matches(Board::new, topology);
+            assert_skipping_matches(|| Board::with_engine(Swar), topology);
+        }
+    }
+}
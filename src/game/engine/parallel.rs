@@ -0,0 +1,355 @@
+use super::{active_chunks, apply_chunks, Engine};
+use crate::game::{
+    chunk_rng, decay, split_coord, Board, Chunk, LargerThanLife, NamedAutomaton, Neighbourhood,
+    Rule, RuleKind, RuleTree, Stochastic, Topology, TransitionTable, CHUNK_SIZE, WIREWORLD_TREE,
+};
+use rand::rngs::StdRng;
+use rayon::prelude::*;
+
+const SIZE: usize = CHUNK_SIZE as usize;
+
+/// Recomputes the chunks that may have changed, spread over the rayon pool.
+#[derive(Debug)]
+pub struct Parallel;
+
+impl Engine for Parallel {
+    fn advance(&mut self, board: &mut Board, rule: &Rule) -> u64 {
+        // Cells of stochastic rules can change anywhere, not just next to
+        // last generation's changes.
+        let mut rules =
+            std::iter::once(rule).chain(board.regions.iter().map(|region| &region.rule));
+        if rules.clone().any(|rule| rule.stochastic().is_some()) {
+            board.dirty = None;
+        }
+        let b0 = rules.any(|rule| rule.has_b0());
+        let layer = RuleLayer::new(board, rule);
+        let next = active_chunks(board, layer.range as i64, b0)
+            .into_par_iter()
+            .map(|key| (key, step_chunk(board, key, &layer)))
+            .collect::<Vec<_>>();
+
+        apply_chunks(board, next);
+
+        1
+    }
+
+    fn supports(&self, rule: &Rule, _topology: Topology) -> bool {
+        rule.blocks().is_none() && rule.wolfram().is_none()
+    }
+}
+
+/// The rules cells run under: the board's own, then the rule of each of its
+/// regions.
+struct RuleLayer<'a> {
+    rules: Vec<Prepared<'a>>,
+    /// The furthest any of the rules looks.
+    range: usize,
+}
+
+/// A rule with what every cell needs from it worked out once per generation.
+enum Prepared<'a> {
+    /// Every rule on the 3x3 neighbourhood runs off the same lookup table.
+    Table(TransitionTable, u16),
+    LargerThanLife(&'a LargerThanLife, u16),
+    Tree(&'a RuleTree),
+    Stochastic(&'a Stochastic, u16, Neighbourhood),
+    Registered(&'a NamedAutomaton),
+}
+
+impl<'a> Prepared<'a> {
+    fn new(rule: &'a Rule) -> Self {
+        match rule.kind() {
+            RuleKind::LifeLike(life_like) | RuleKind::Map(life_like) => {
+                Prepared::Table(life_like.table(), life_like.states())
+            }
+            RuleKind::LtL(ltl, states) => Prepared::LargerThanLife(ltl, *states),
+            RuleKind::File(file) => Prepared::Tree(&file.tree),
+            RuleKind::WireWorld => Prepared::Tree(&WIREWORLD_TREE),
+            RuleKind::Stochastic {
+                stochastic,
+                states,
+                neighbourhood,
+            } => Prepared::Stochastic(stochastic, *states, *neighbourhood),
+            RuleKind::Registered(named) => Prepared::Registered(named),
+            RuleKind::Block(_) | RuleKind::Wolfram(_) => {
+                panic!("block and 1D rules run on their own engines")
+            }
+        }
+    }
+}
+
+impl<'a> RuleLayer<'a> {
+    fn new(board: &'a Board, rule: &'a Rule) -> Self {
+        let rules = std::iter::once(rule).chain(board.regions.iter().map(|region| &region.rule));
+        let range = rules
+            .clone()
+            .map(|rule| rule.range() as usize)
+            .max()
+            .unwrap_or(1);
+
+        RuleLayer {
+            rules: rules.map(Prepared::new).collect(),
+            range,
+        }
+    }
+    /// The rule of the cell.
+    fn at(&self, board: &Board, x: i64, y: i64) -> &Prepared<'a> {
+        let index = match self.rules.len() {
+            1 => 0,
+            _ => board
+                .regions
+                .iter()
+                .rposition(|region| region.contains(x, y))
+                .map_or(0, |i| i + 1),
+        };
+        &self.rules[index]
+    }
+}
+
+/// The states of the cells of a chunk and a border of `border` cells around
+/// it, one row after another starting from the bottom left of the border.
+pub(super) struct Padded {
+    cells: Vec<u8>,
+    width: usize,
+}
+
+impl Padded {
+    pub(super) fn gather(
+        board: &Board,
+        (cx, cy): (i64, i64),
+        border: usize,
+        interior: bool,
+    ) -> Self {
+        let width = SIZE + 2 * border;
+        let mut cells = vec![0; width * width];
+        let (x0, y0) = (cx * CHUNK_SIZE, cy * CHUNK_SIZE);
+        let offset = |i: usize| i as i64 - border as i64;
+
+        if interior {
+            let neighbours: [[Option<&Chunk>; 3]; 3] =
+                [-1, 0, 1].map(|dy| [-1, 0, 1].map(|dx| board.chunks.get(&(cx + dx, cy + dy))));
+
+            for (i, cell) in cells.iter_mut().enumerate() {
+                let ((kx, lx), (ky, ly)) = (
+                    split_coord(offset(i % width)),
+                    split_coord(offset(i / width)),
+                );
+                *cell = neighbours[(ky + 1) as usize][(kx + 1) as usize]
+                    .map_or(0, |chunk| chunk.state(lx, ly));
+            }
+        } else {
+            for (i, cell) in cells.iter_mut().enumerate() {
+                *cell = board.state_resolved(x0 + offset(i % width), y0 + offset(i / width));
+            }
+        }
+
+        Padded { cells, width }
+    }
+    /// Whether the cell is alive, i.e. in state 1.
+    pub(super) fn get(&self, x: usize, y: usize) -> bool {
+        self.state(x, y) == 1
+    }
+    fn state(&self, x: usize, y: usize) -> u8 {
+        self.cells[y * self.width + x]
+    }
+    /// The live neighbours of the cell at `(x + 1, y + 1)` as a bit pattern,
+    /// NW, N, NE, W, E, SW, S, SE from the most significant bit.
+    fn neighbours(&self, x: usize, y: usize) -> u8 {
+        let mut pattern = 0;
+        for dy in [2, 1, 0] {
+            for dx in 0..3 {
+                if (dx, dy) != (1, 1) {
+                    pattern = (pattern << 1) | self.get(x + dx, y + dy) as u8;
+                }
+            }
+        }
+        pattern
+    }
+}
+
+/// Prefix sums of a [`Padded`] grid, so that the live cells in any rectangle
+/// can be counted in constant time.
+struct SummedArea {
+    sums: Vec<u32>,
+    width: usize,
+}
+
+impl SummedArea {
+    fn new(padded: &Padded) -> Self {
+        let width = padded.width + 1;
+        let mut sums = vec![0; width * width];
+
+        for y in 0..padded.width {
+            let mut row = 0;
+            for x in 0..padded.width {
+                row += padded.get(x, y) as u32;
+                sums[(y + 1) * width + x + 1] = sums[y * width + x + 1] + row;
+            }
+        }
+
+        SummedArea { sums, width }
+    }
+    /// Live cells with `x0 <= x <= x1` and `y0 <= y <= y1`.
+    fn count(&self, (x0, y0): (usize, usize), (x1, y1): (usize, usize)) -> u32 {
+        let at = |x: usize, y: usize| self.sums[y * self.width + x];
+        at(x1 + 1, y1 + 1) + at(x0, y0) - at(x0, y1 + 1) - at(x1 + 1, y0)
+    }
+}
+
+/// Computes the next state of one chunk from it and a border as wide as the
+/// range of the rules.
+fn step_chunk(board: &Board, (cx, cy): (i64, i64), layer: &RuleLayer) -> Chunk {
+    let (x0, y0) = (cx * CHUNK_SIZE, cy * CHUNK_SIZE);
+    let topology = board.topology;
+    let range = layer.range;
+    let r = range as i64;
+    let interior = topology.contains(x0 - r, y0 - r)
+        && topology.contains(x0 + CHUNK_SIZE - 1 + r, y0 + CHUNK_SIZE - 1 + r);
+
+    let padded = Padded::gather(board, (cx, cy), range, interior);
+    let mut summed = None;
+    let mut rng = None;
+    let own = board.chunks.get(&(cx, cy));
+    let mut next = Chunk::new();
+    for y in 0..SIZE {
+        for x in 0..SIZE {
+            if !interior && !topology.contains(x0 + x as i64, y0 + y as i64) {
+                continue;
+            }
+
+            let state = own.map_or(0, |chunk| chunk.state(x, y));
+            let cell = Cell {
+                padded: &padded,
+                x,
+                y,
+                range,
+                state,
+            };
+            let state = match layer.at(board, x0 + x as i64, y0 + y as i64) {
+                Prepared::Table(table, states) => next_table(cell, table, *states),
+                Prepared::LargerThanLife(ltl, states) => {
+                    let summed = summed.get_or_insert_with(|| SummedArea::new(&padded));
+                    next_larger_than_life(cell, ltl, *states, summed)
+                }
+                Prepared::Tree(tree) => next_tree(cell, tree),
+                Prepared::Stochastic(stochastic, states, neighbourhood) => {
+                    let rng = rng
+                        .get_or_insert_with(|| chunk_rng(board.seed, board.generation, (cx, cy)));
+                    next_stochastic(cell, stochastic, *states, *neighbourhood, rng)
+                }
+                Prepared::Registered(named) => next_registered(cell, named),
+            };
+
+            if state != 0 {
+                next.set_state(x, y, state);
+            }
+        }
+    }
+
+    next
+}
+
+/// A cell of a chunk being stepped, at `(x, y)` within it.
+#[derive(Clone, Copy)]
+struct Cell<'a> {
+    padded: &'a Padded,
+    x: usize,
+    y: usize,
+    /// How wide the border of `padded` is.
+    range: usize,
+    state: u8,
+}
+
+impl Cell<'_> {
+    /// The state of the neighbour `(dx, dy)` away.
+    fn neighbour(&self, dx: i64, dy: i64) -> u8 {
+        let r = self.range as i64;
+        self.padded.state(
+            (self.x as i64 + r + dx) as usize,
+            (self.y as i64 + r + dy) as usize,
+        )
+    }
+    /// The live cells of the 3x3 neighbourhood as a bit pattern.
+    fn neighbours(&self) -> u8 {
+        let r = self.range - 1;
+        self.padded.neighbours(self.x + r, self.y + r)
+    }
+}
+
+fn next_table(cell: Cell, table: &TransitionTable, states: u16) -> u8 {
+    let alive = table.get(cell.state == 1, cell.neighbours());
+    decay(states, cell.state, alive)
+}
+
+fn next_larger_than_life(cell: Cell, ltl: &LargerThanLife, states: u16, summed: &SummedArea) -> u8 {
+    let count = count_range(ltl, summed, (cell.x + cell.range, cell.y + cell.range));
+    let count = count - (!ltl.middle && cell.state == 1) as u16;
+    decay(states, cell.state, ltl.alive_next(cell.state == 1, count))
+}
+
+fn next_tree(cell: Cell, tree: &RuleTree) -> u8 {
+    tree.next_state(|dx, dy| cell.neighbour(dx, dy))
+}
+
+fn next_stochastic(
+    cell: Cell,
+    stochastic: &Stochastic,
+    states: u16,
+    neighbourhood: Neighbourhood,
+    rng: &mut StdRng,
+) -> u8 {
+    let count = cell.neighbours() & neighbourhood.mask();
+    let alive = stochastic.alive_next(cell.state == 1, count.count_ones(), rng);
+    decay(states, cell.state, alive)
+}
+
+fn next_registered(cell: Cell, named: &NamedAutomaton) -> u8 {
+    let neighbourhood = named.automaton.neighbourhood();
+    named.automaton.step(cell.state, &|i| {
+        let (dx, dy) = neighbourhood[i];
+        cell.neighbour(dx, dy)
+    })
+}
+
+/// Live cells in the neighbourhood around `(x, y)`, including the cell itself.
+fn count_range(ltl: &LargerThanLife, summed: &SummedArea, (x, y): (usize, usize)) -> u16 {
+    let r = ltl.range as usize;
+    let count = match ltl.neighbourhood {
+        Neighbourhood::Moore => summed.count((x - r, y - r), (x + r, y + r)),
+        _ => ltl
+            .rows()
+            .map(|(dy, dx)| {
+                let row = (y as i64 + dy) as usize;
+                let (min, max) = (x as i64 + dx.start(), x as i64 + dx.end());
+                summed.count((min as usize, row), (max as usize, row))
+            })
+            .sum(),
+    };
+    count as u16
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{Region, Sequential};
+
+    #[test]
+    fn life_like_cells_ignore_wider_regions_elsewhere() {
+        let rule: Rule = "B3/S23".parse().unwrap();
+        let mut board = Board::soup(Topology::Plane, 1);
+        let mut expected = board.clone();
+
+        // Far enough away that nothing reaches it, but wide enough to widen
+        // the border every chunk is gathered with.
+        board.regions.push(Region {
+            min: (1000, 1000),
+            max: (1010, 1010),
+            rule: "R5,C0,M1,S34..58,B34..45,NM".parse().unwrap(),
+        });
+        for _ in 0..10 {
+            Parallel.advance(&mut board, &rule);
+            Sequential.advance(&mut expected, &rule);
+        }
+        assert_eq!(board.sorted_cells(), expected.sorted_cells());
+    }
+}
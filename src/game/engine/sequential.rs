@@ -0,0 +1,55 @@
+use super::Engine;
+use crate::game::{Board, Rule, Topology};
+use std::collections::HashSet;
+
+/// Only visits live cells and their neighbours, which is cheap for sparse boards.
+#[derive(Debug)]
+pub struct Sequential;
+
+impl Engine for Sequential {
+    fn advance(&mut self, board: &mut Board, rule: &Rule) -> u64 {
+        let automaton = rule
+            .automaton()
+            .expect("only life-like rules and registered automata are supported");
+        let neighbourhood = automaton.neighbourhood();
+
+        // Cells that aren't empty and every cell that has one of them as
+        // a neighbour.
+        let mut active_tiles = board
+            .cells()
+            .map(|(x, y, _)| (x, y))
+            .collect::<HashSet<_>>();
+        board.cells().for_each(|(x, y, _)| {
+            neighbourhood.iter().for_each(|(dx, dy)| {
+                if let Some(tile) = board.topology.resolve(x - dx, y - dy) {
+                    active_tiles.insert(tile);
+                }
+            })
+        });
+        // Empty space comes alive too under B0 rules, which bounded planes
+        // don't emulate.
+        if let Some(((x0, y0), (x1, y1))) = board.topology.bounds().filter(|_| rule.has_b0()) {
+            active_tiles.extend((y0..=y1).flat_map(|y| (x0..=x1).map(move |x| (x, y))));
+        }
+
+        let mut next = board.clone();
+        next.clear();
+
+        for (x, y) in active_tiles.into_iter() {
+            let state = automaton.step(board.state(x, y), &|i| {
+                let (dx, dy) = neighbourhood[i];
+                board.state_resolved(x + dx, y + dy)
+            });
+            if state != 0 {
+                next.set_state(x, y, state);
+            }
+        }
+
+        board.replace_chunks(next.chunks);
+
+        1
+    }
+    fn supports(&self, rule: &Rule, _topology: Topology) -> bool {
+        rule.automaton().is_some()
+    }
+}
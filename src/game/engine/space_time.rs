@@ -0,0 +1,74 @@
+use super::Engine;
+use crate::game::{split_coord, Board, Rule, Topology, CHUNK_SIZE};
+use std::collections::{BTreeSet, HashSet};
+
+/// Runs 1D rules as a space-time diagram: generation `n` is the row at
+/// `y = -n`, so every generation writes one row below the last.
+#[derive(Debug)]
+pub struct SpaceTime;
+
+impl Engine for SpaceTime {
+    fn advance(&mut self, board: &mut Board, rule: &Rule) -> u64 {
+        let wolfram = rule.wolfram().expect("only 1D rules are supported");
+        let range = wolfram.range as i64;
+        let y = current_row(board);
+        let Some((_, below)) = board.topology.resolve(0, y - 1) else {
+            // The diagram ran off the bottom of a bounded plane.
+            return 1;
+        };
+
+        let live = live_in_row(board, y);
+        let candidates = live
+            .iter()
+            .flat_map(|&x| x - range..=x + range)
+            .collect::<BTreeSet<_>>();
+        let next = candidates
+            .into_iter()
+            .filter(|&x| board.topology.contains(x, y))
+            .filter(|&x| {
+                wolfram.alive_next((x - range..=x + range).map(|x| board.get_resolved(x, y)))
+            })
+            .collect::<Vec<_>>();
+
+        // Tori wrap the diagram round, over older generations.
+        for x in live_in_row(board, below) {
+            board.set(x, below, false);
+        }
+        for &x in &next {
+            board.set(x, below, true);
+        }
+        board.active_chunks = next
+            .iter()
+            .map(|&x| split_coord(x).0)
+            .collect::<HashSet<_>>()
+            .len();
+
+        1
+    }
+
+    fn supports(&self, rule: &Rule, _topology: Topology) -> bool {
+        rule.wolfram().is_some()
+    }
+}
+
+/// The row holding the board's generation, wrapped onto bounded topologies.
+pub fn current_row(board: &Board) -> i64 {
+    let y = -(board.generation as i64);
+    board.topology.resolve(0, y).map_or(y, |(_, y)| y)
+}
+
+/// The live cells on row `y`, from west to east.
+fn live_in_row(board: &Board, y: i64) -> BTreeSet<i64> {
+    let (cy, ly) = split_coord(y);
+    board
+        .chunks
+        .iter()
+        .filter(|(&(_, chunk_y), _)| chunk_y == cy)
+        .flat_map(|(&(cx, _), chunk)| {
+            chunk
+                .cells()
+                .filter(move |&(_, cell_y)| cell_y == ly)
+                .map(move |(x, _)| cx * CHUNK_SIZE + x as i64)
+        })
+        .collect()
+}
@@ -0,0 +1,226 @@
+use super::{active_chunks, apply_chunks, Engine};
+use crate::game::{split_coord, Board, Chunk, Rule, Topology, CHUNK_SIZE};
+use rayon::prelude::*;
+use std::collections::HashMap;
+
+const SIZE: usize = CHUNK_SIZE as usize;
+
+/// Works directly on the `u64` rows of each chunk, counting the neighbours of
+/// 64 cells at once with bitwise adders. Each row of chunks is a rayon job.
+#[derive(Debug)]
+pub struct Swar;
+
+impl Engine for Swar {
+    fn advance(&mut self, board: &mut Board, rule: &Rule) -> u64 {
+        let rule = rule
+            .life_like()
+            .expect("only totalistic rules are supported");
+        let (mut born, mut survive) = (0u16, 0u16);
+        for count in 0..=8 {
+            born |= (rule.born(count) as u16) << count;
+            survive |= (rule.survive(count) as u16) << count;
+        }
+
+        let mask = rule.neighbourhood().mask();
+
+        let mut bands: HashMap<i64, Vec<i64>> = HashMap::new();
+        for (cx, cy) in active_chunks(board, 1, false) {
+            bands.entry(cy).or_default().push(cx);
+        }
+
+        let current = &*board;
+        let next = bands
+            .into_par_iter()
+            .flat_map_iter(|(cy, band)| {
+                band.into_iter()
+                    .map(move |cx| ((cx, cy), step_chunk(current, (cx, cy), born, survive, mask)))
+            })
+            .collect::<Vec<_>>();
+
+        apply_chunks(board, next);
+
+        1
+    }
+    fn supports(&self, rule: &Rule, _topology: Topology) -> bool {
+        rule.states() == 2 && rule.is_totalistic() && !rule.has_b0()
+    }
+}
+
+/// A chunk's rows plus a one cell border: `rows[i]` is world row `y0 - 1 + i`,
+/// and `west`/`east` hold the cells just past either end of that row.
+struct Padded {
+    rows: [u64; SIZE + 2],
+    west: [u64; SIZE + 2],
+    east: [u64; SIZE + 2],
+}
+
+impl Padded {
+    fn gather(board: &Board, (cx, cy): (i64, i64), interior: bool) -> Self {
+        let mut padded = Padded {
+            rows: [0; SIZE + 2],
+            west: [0; SIZE + 2],
+            east: [0; SIZE + 2],
+        };
+
+        if interior {
+            let chunk = |dx: i64, dy: i64| board.chunks.get(&(cx + dx, cy + dy));
+
+            for i in 0..SIZE + 2 {
+                let (dy, y) = split_coord(i as i64 - 1);
+                padded.rows[i] = chunk(0, dy).map_or(0, |c| c.rows[y]);
+                padded.west[i] = chunk(-1, dy).map_or(0, |c| c.rows[y] >> (SIZE - 1));
+                padded.east[i] = chunk(1, dy).map_or(0, |c| c.rows[y] & 1);
+            }
+        } else {
+            let (x0, y0) = (cx * CHUNK_SIZE, cy * CHUNK_SIZE);
+
+            for i in 0..SIZE + 2 {
+                let y = y0 + i as i64 - 1;
+                for x in 0..SIZE {
+                    padded.rows[i] |= (board.get_resolved(x0 + x as i64, y) as u64) << x;
+                }
+                padded.west[i] = board.get_resolved(x0 - 1, y) as u64;
+                padded.east[i] = board.get_resolved(x0 + CHUNK_SIZE, y) as u64;
+            }
+        }
+
+        padded
+    }
+    /// The three horizontally adjacent cells of each cell in row `i`, shifted
+    /// so that bit `x` of each word lines up with cell `x`.
+    fn shifted(&self, i: usize) -> (u64, u64, u64) {
+        let row = self.rows[i];
+        (
+            (row << 1) | self.west[i],
+            row,
+            (row >> 1) | (self.east[i] << (SIZE - 1)),
+        )
+    }
+}
+
+/// Adds a one bit value to every lane of a 4 bit counter stored as bit planes.
+fn add(counter: &mut [u64; 4], bits: u64) {
+    let mut carry = bits;
+    for plane in counter.iter_mut() {
+        let next = *plane & carry;
+        *plane ^= carry;
+        carry = next;
+    }
+}
+
+/// Lanes of `counter` that are equal to `n`.
+fn equals(counter: &[u64; 4], n: u16) -> u64 {
+    counter.iter().enumerate().fold(!0, |acc, (bit, plane)| {
+        acc & if (n >> bit) & 1 == 1 { *plane } else { !*plane }
+    })
+}
+
+fn step_chunk(board: &Board, (cx, cy): (i64, i64), born: u16, survive: u16, mask: u8) -> Chunk {
+    let (x0, y0) = (cx * CHUNK_SIZE, cy * CHUNK_SIZE);
+    let topology = board.topology;
+    let interior =
+        topology.contains(x0 - 1, y0 - 1) && topology.contains(x0 + CHUNK_SIZE, y0 + CHUNK_SIZE);
+
+    let padded = Padded::gather(board, (cx, cy), interior);
+    let mut next = Chunk::new();
+
+    for (y, row) in next.rows.iter_mut().enumerate() {
+        let mut counter = [0; 4];
+        let (sw, s, se) = padded.shifted(y);
+        let (w, alive, e) = padded.shifted(y + 1);
+        let (nw, n, ne) = padded.shifted(y + 2);
+
+        for (i, bits) in [nw, n, ne, w, e, sw, s, se].into_iter().enumerate() {
+            if (mask >> (7 - i)) & 1 == 1 {
+                add(&mut counter, bits);
+            }
+        }
+
+        let (mut born_lanes, mut survive_lanes) = (0, 0);
+        for count in 0..=8 {
+            let lanes = equals(&counter, count);
+            if (born >> count) & 1 == 1 {
+                born_lanes |= lanes;
+            }
+            if (survive >> count) & 1 == 1 {
+                survive_lanes |= lanes;
+            }
+        }
+
+        *row = (!alive & born_lanes) | (alive & survive_lanes);
+
+        if !interior {
+            for x in 0..SIZE {
+                if !topology.contains(x0 + x as i64, y0 + y as i64) {
+                    *row &= !(1 << x);
+                }
+            }
+        }
+    }
+
+    next
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{Parallel, Sequential, Topology};
+
+    /// A random soup over the topology, plus on the plane blinkers across
+    /// the chunk boundary at x = 63/64.
+    fn soup(topology: Topology, seed: u64) -> Board {
+        let mut board = Board::soup(topology, seed);
+        if topology == Topology::Plane {
+            for y in [150, 200] {
+                for x in [62, 63, 64] {
+                    board.set(x, y, true);
+                }
+            }
+            for x in [63, 64] {
+                for y in [-150, -149, -148] {
+                    board.set(x, y, true);
+                }
+            }
+        }
+        board
+    }
+
+    #[test]
+    fn matches_the_classic_engines() {
+        for rulestring in ["B3/S23", "B36/S23"] {
+            let rule: Rule = rulestring.parse().unwrap();
+
+            // Once lined up with chunks so the edges fall on the ends of the
+            // words, and once not.
+            let topologies = [Topology::Plane]
+                .into_iter()
+                .chain(Topology::every_bounded(128, 128))
+                .chain(Topology::every_bounded(90, 70));
+            for topology in topologies {
+                let mut swar = soup(topology, 7);
+                let mut parallel = swar.clone();
+                let mut sequential = swar.clone();
+
+                for generation in 1..=40 {
+                    Swar.advance(&mut swar, &rule);
+                    Parallel.advance(&mut parallel, &rule);
+                    Sequential.advance(&mut sequential, &rule);
+
+                    let context = format!(
+                        "{} on {} after {} generations",
+                        rulestring,
+                        topology.serialize(),
+                        generation
+                    );
+                    assert_eq!(swar.sorted_cells(), parallel.sorted_cells(), "{}", context);
+                    assert_eq!(
+                        swar.sorted_cells(),
+                        sequential.sorted_cells(),
+                        "{}",
+                        context
+                    );
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,178 @@
+use rayon::prelude::*;
+use std::f64::consts::PI;
+use std::ops::{Add, Mul, Sub};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(super) struct Complex {
+    pub re: f32,
+    pub im: f32,
+}
+
+impl Complex {
+    pub fn new(re: f32, im: f32) -> Self {
+        Complex { re, im }
+    }
+    fn conj(self) -> Self {
+        Complex::new(self.re, -self.im)
+    }
+}
+
+impl Add for Complex {
+    type Output = Self;
+    fn add(self, other: Self) -> Self {
+        Complex::new(self.re + other.re, self.im + other.im)
+    }
+}
+
+impl Sub for Complex {
+    type Output = Self;
+    fn sub(self, other: Self) -> Self {
+        Complex::new(self.re - other.re, self.im - other.im)
+    }
+}
+
+impl Mul for Complex {
+    type Output = Self;
+    fn mul(self, other: Self) -> Self {
+        Complex::new(
+            self.re * other.re - self.im * other.im,
+            self.re * other.im + self.im * other.re,
+        )
+    }
+}
+
+/// Radix-2 Fourier transforms of square grids whose side is a power of two,
+/// one row after another. Rows and then columns are transformed in parallel.
+#[derive(Clone, Debug)]
+pub(super) struct Fft {
+    size: usize,
+    /// `e^(-2πik/size)` for the first half of `k`.
+    twiddles: Vec<Complex>,
+}
+
+impl Fft {
+    pub fn new(size: usize) -> Self {
+        assert!(size.is_power_of_two(), "FFT sizes must be powers of two");
+        let twiddles = (0..size / 2)
+            .map(|k| {
+                let angle = -2. * PI * k as f64 / size as f64;
+                Complex::new(angle.cos() as f32, angle.sin() as f32)
+            })
+            .collect();
+
+        Fft { size, twiddles }
+    }
+
+    pub fn forward(&self, grid: &mut [Complex]) {
+        self.transform(grid, false);
+    }
+
+    pub fn inverse(&self, grid: &mut [Complex]) {
+        self.transform(grid, true);
+
+        let scale = 1. / grid.len() as f32;
+        grid.par_iter_mut().for_each(|value| {
+            value.re *= scale;
+            value.im *= scale;
+        });
+    }
+
+    fn transform(&self, grid: &mut [Complex], inverse: bool) {
+        debug_assert_eq!(grid.len(), self.size * self.size);
+
+        for _ in 0..2 {
+            grid.par_chunks_mut(self.size)
+                .for_each(|row| self.transform_row(row, inverse));
+            transpose(grid, self.size);
+        }
+    }
+
+    fn transform_row(&self, row: &mut [Complex], inverse: bool) {
+        let n = row.len();
+        let bits = n.trailing_zeros();
+        if bits == 0 {
+            return;
+        }
+        for i in 0..n {
+            let j = i.reverse_bits() >> (usize::BITS - bits);
+            if i < j {
+                row.swap(i, j);
+            }
+        }
+
+        let mut len = 2;
+        while len <= n {
+            let half = len / 2;
+            let step = n / len;
+            for start in (0..n).step_by(len) {
+                for k in 0..half {
+                    let twiddle = self.twiddles[k * step];
+                    let twiddle = if inverse { twiddle.conj() } else { twiddle };
+                    let even = row[start + k];
+                    let odd = row[start + k + half] * twiddle;
+                    row[start + k] = even + odd;
+                    row[start + k + half] = even - odd;
+                }
+            }
+            len *= 2;
+        }
+    }
+}
+
+fn transpose(grid: &mut [Complex], size: usize) {
+    for y in 0..size {
+        for x in y + 1..size {
+            grid.swap(y * size + x, x * size + y);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The 2D DFT straight from its definition.
+    fn naive_dft(grid: &[Complex], size: usize) -> Vec<Complex> {
+        (0..size * size)
+            .map(|out| {
+                let (u, v) = (out % size, out / size);
+                grid.iter()
+                    .enumerate()
+                    .fold((0., 0.), |(re, im), (i, value)| {
+                        let (x, y) = (i % size, i / size);
+                        let angle = -2. * PI * ((u * x + v * y) % size) as f64 / size as f64;
+                        let (sin, cos) = angle.sin_cos();
+                        let (a, b) = (value.re as f64, value.im as f64);
+                        (re + a * cos - b * sin, im + a * sin + b * cos)
+                    })
+            })
+            .map(|(re, im)| Complex::new(re as f32, im as f32))
+            .collect()
+    }
+
+    fn assert_close(actual: &[Complex], expected: &[Complex]) {
+        for (i, (a, b)) in actual.iter().zip(expected).enumerate() {
+            assert!(
+                (a.re - b.re).abs() < 1e-3 && (a.im - b.im).abs() < 1e-3,
+                "at {i}: {a:?} != {b:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn matches_a_naive_dft() {
+        for size in [1, 2, 8, 16] {
+            let grid = (0..size * size)
+                .map(|i| Complex::new(((i * 7) % 11) as f32 / 10., ((i * 3) % 5) as f32 / 4.))
+                .collect::<Vec<_>>();
+
+            let fft = Fft::new(size);
+            let mut transformed = grid.clone();
+            fft.forward(&mut transformed);
+            assert_close(&transformed, &naive_dft(&grid, size));
+
+            fft.inverse(&mut transformed);
+            assert_close(&transformed, &grid);
+        }
+    }
+}
@@ -0,0 +1,215 @@
+use super::fft::{Complex, Fft};
+use super::ContinuousRule;
+use rand::Rng;
+use rayon::prelude::*;
+
+/// A square, wrapping field of cells holding values from 0 to 1, for
+/// continuous automata. Cell `(0, 0)` of the board sits in the middle.
+///
+/// Every generation convolves the field with the rule's kernels through
+/// FFTs, so large kernels cost no more than small ones.
+#[derive(Clone, Debug)]
+pub struct Field {
+    size: usize,
+    cells: Vec<f32>,
+    pub generation: u64,
+    rule: ContinuousRule,
+    /// Transforms of the rule's kernels, normalised and centred on cell 0.
+    kernels: Vec<Vec<Complex>>,
+    fft: Fft,
+}
+
+impl Field {
+    /// An empty field of `size` cells along each side, rounded up to a power
+    /// of two.
+    pub fn new(rule: ContinuousRule, size: usize) -> Self {
+        let size = size.max(2).next_power_of_two();
+        let fft = Fft::new(size);
+
+        let kernels = (0..rule.kernel_count())
+            .map(|index| {
+                let mut kernel = vec![Complex::default(); size * size];
+                for (i, weight) in kernel.iter_mut().enumerate() {
+                    // Offsets past the middle wrap round to negative ones.
+                    let offset = |c: usize| {
+                        let c = c as f32;
+                        if c < size as f32 / 2. {
+                            c
+                        } else {
+                            c - size as f32
+                        }
+                    };
+                    let (dx, dy) = (offset(i % size), offset(i / size));
+                    weight.re = rule.kernel(index, dx.hypot(dy));
+                }
+
+                let total = kernel.iter().map(|weight| weight.re).sum::<f32>();
+                if total > 0. {
+                    kernel.iter_mut().for_each(|weight| weight.re /= total);
+                }
+                fft.forward(&mut kernel);
+                kernel
+            })
+            .collect();
+
+        Field {
+            size,
+            cells: vec![0.; size * size],
+            generation: 0,
+            rule,
+            kernels,
+            fft,
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// The board coordinates of the lowest corner of the field.
+    pub fn origin(&self) -> i64 {
+        -(self.size as i64 / 2)
+    }
+
+    fn index(&self, x: i64, y: i64) -> usize {
+        let wrap = |c: i64| (c - self.origin()).rem_euclid(self.size as i64) as usize;
+        wrap(y) * self.size + wrap(x)
+    }
+
+    pub fn get(&self, x: i64, y: i64) -> f32 {
+        self.cells[self.index(x, y)]
+    }
+
+    pub fn set(&mut self, x: i64, y: i64, value: f32) {
+        let index = self.index(x, y);
+        self.cells[index] = value.clamp(0., 1.);
+    }
+
+    /// Sets every cell in a disk around `(x, y)`, a quarter of the kernels
+    /// across, so that a click leaves something the rule can work with.
+    pub fn paint(&mut self, x: i64, y: i64, value: f32) {
+        let r = (self.rule.radius() / 4.).max(1.);
+        let reach = r.ceil() as i64;
+        for dy in -reach..=reach {
+            for dx in -reach..=reach {
+                if (dx as f32).hypot(dy as f32) <= r {
+                    self.set(x + dx, y + dy, value);
+                }
+            }
+        }
+    }
+
+    /// The sum of every cell.
+    pub fn mass(&self) -> f32 {
+        self.cells.par_iter().sum()
+    }
+
+    pub fn clear(&mut self) {
+        self.cells.fill(0.);
+    }
+
+    /// Scatters squares over the field: sparse noise twice as wide as the
+    /// kernels for Lenia, which grows out of it, and denser solid ones as
+    /// wide as the kernels for SmoothLife, where noise averages out to
+    /// nothing.
+    pub fn randomize(&mut self, rng: &mut impl Rng) {
+        self.clear();
+
+        let solid = matches!(self.rule, ContinuousRule::SmoothLife { .. });
+        let (width, spread) = if solid { (1., 4) } else { (2., 16) };
+        let side = ((width * self.rule.radius()) as usize).clamp(1, self.size);
+        let patches = (self.size * self.size / (spread * side * side)).max(1);
+        for _ in 0..patches {
+            let (x0, y0) = (
+                rng.gen_range(0..self.size) as i64,
+                rng.gen_range(0..self.size) as i64,
+            );
+            for y in y0..y0 + side as i64 {
+                for x in x0..x0 + side as i64 {
+                    let value = if solid { 1. } else { rng.gen() };
+                    self.set(x + self.origin(), y + self.origin(), value);
+                }
+            }
+        }
+    }
+
+    /// The weighted average of the field under each kernel, around every
+    /// cell.
+    fn potentials(&self) -> Vec<Vec<f32>> {
+        let mut spectrum = self
+            .cells
+            .par_iter()
+            .map(|&value| Complex::new(value, 0.))
+            .collect::<Vec<_>>();
+        self.fft.forward(&mut spectrum);
+
+        self.kernels
+            .iter()
+            .map(|kernel| {
+                let mut potential = spectrum
+                    .par_iter()
+                    .zip(kernel)
+                    .map(|(&a, &b)| a * b)
+                    .collect::<Vec<_>>();
+                self.fft.inverse(&mut potential);
+                potential.into_par_iter().map(|value| value.re).collect()
+            })
+            .collect()
+    }
+
+    pub fn step(&mut self) {
+        let potentials = self.potentials();
+
+        let rule = &self.rule;
+        self.cells
+            .par_iter_mut()
+            .enumerate()
+            .for_each(|(i, value)| *value = rule.next(*value, |kernel| potentials[kernel][i]));
+        self.generation += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn potentials_match_a_naive_convolution() {
+        let rule = ContinuousRule::SmoothLife {
+            outer_radius: 4.,
+            birth: [0.278, 0.365],
+            death: [0.267, 0.445],
+            alpha_n: 0.028,
+            alpha_m: 0.147,
+            dt: 0.2,
+        };
+        let mut field = Field::new(rule.clone(), 16);
+        let size = field.size() as i64;
+        for y in 0..size {
+            for x in 0..size {
+                field.set(x, y, ((x * 7 + y * 13) % 11) as f32 / 10.);
+            }
+        }
+
+        let potentials = field.potentials();
+        let offsets =
+            || (-size / 2..size / 2).flat_map(|dy| (-size / 2..size / 2).map(move |dx| (dx, dy)));
+        for (index, potential) in potentials.iter().enumerate() {
+            let weight = |(dx, dy): (i64, i64)| rule.kernel(index, (dx as f32).hypot(dy as f32));
+            let total = offsets().map(weight).sum::<f32>();
+            for y in 0..size {
+                for x in 0..size {
+                    let expected = offsets()
+                        .map(|(dx, dy)| field.get(x - dx, y - dy) * weight((dx, dy)))
+                        .sum::<f32>()
+                        / total;
+                    let actual = potential[field.index(x, y)];
+                    assert!(
+                        (actual - expected).abs() < 1e-4,
+                        "kernel {index} at ({x}, {y}): {actual} != {expected}"
+                    );
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,263 @@
+//! Hensel notation for isotropic non-totalistic rules, e.g. `B2-a/S12`.
+//!
+//! Neighbourhoods are 8 bit patterns, from the most significant bit down:
+//! NW, N, NE, W, E, SW, S, SE.
+
+use super::{RuleError, RuleErrorKind};
+
+/// The letters of each neighbour count, in canonical order.
+const LETTERS: [&str; 9] = [
+    "",
+    "ce",
+    "cekain",
+    "cekainyqjr",
+    "cekainyqjrtwz",
+    "cekainyqjr",
+    "cekain",
+    "ce",
+    "",
+];
+
+/// One neighbourhood for every count and letter, in the same order as
+/// `LETTERS`. The rest are its rotations and reflections.
+const REPRESENTATIVES: [&[u8]; 9] = [
+    &[],
+    &[0x01, 0x02],
+    &[0x05, 0x0a, 0x0c, 0x03, 0x18, 0x24],
+    &[0x25, 0x1a, 0x32, 0x0b, 0x07, 0x0d, 0x31, 0x26, 0x0e, 0x19],
+    &[
+        0xa5, 0x5a, 0x33, 0x0f, 0x1d, 0x27, 0x35, 0x36, 0x3a, 0x1b, 0x39, 0x2e, 0x3c,
+    ],
+    &[0x5b, 0xa7, 0x75, 0x2f, 0x1f, 0x3b, 0x5d, 0x3e, 0x37, 0x3d],
+    &[0x5f, 0xaf, 0x77, 0x3f, 0xbd, 0x7e],
+    &[0x7f, 0xbf],
+    &[],
+];
+
+/// Offsets of the neighbours, from the most significant bit down.
+const OFFSETS: [(i8, i8); 8] = [
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+    (-1, 0),
+    (1, 0),
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+];
+
+fn transform(pattern: u8, f: impl Fn((i8, i8)) -> (i8, i8)) -> u8 {
+    let mut out = 0;
+    for (i, offset) in OFFSETS.iter().enumerate() {
+        if (pattern >> (7 - i)) & 1 == 1 {
+            let j = OFFSETS.iter().position(|o| *o == f(*offset)).unwrap();
+            out |= 1 << (7 - j);
+        }
+    }
+    out
+}
+
+/// Every rotation and reflection of a neighbourhood.
+fn symmetries(pattern: u8) -> Vec<u8> {
+    let mut out = Vec::with_capacity(8);
+    let mut p = pattern;
+    for _ in 0..4 {
+        out.push(p);
+        out.push(transform(p, |(x, y)| (-x, y)));
+        p = transform(p, |(x, y)| (-y, x));
+    }
+    out.sort_unstable();
+    out.dedup();
+    out
+}
+
+/// All neighbourhoods with `count` live cells matching `letter`.
+fn letter_patterns(count: usize, letter: char) -> Option<Vec<u8>> {
+    let i = LETTERS[count].find(letter)?;
+    Some(symmetries(REPRESENTATIVES[count][i]))
+}
+
+/// Whether a set of neighbourhoods looks the same after any rotation or
+/// reflection, i.e. whether Hensel notation can describe it.
+pub fn is_isotropic(set: &[bool; 256]) -> bool {
+    (0..=255u8).all(|pattern| {
+        symmetries(pattern)
+            .into_iter()
+            .all(|p| set[p as usize] == set[pattern as usize])
+    })
+}
+
+/// Whether `letter` names some of the neighbourhoods with `count` live cells.
+pub fn is_letter_of(count: u8, letter: char) -> bool {
+    LETTERS
+        .get(count as usize)
+        .is_some_and(|letters| letters.contains(letter))
+}
+
+/// Parses the conditions of one half of a rule, like `2-a` or `12ce3`, into
+/// the set of neighbourhoods it covers. Errors point into `text`.
+pub fn parse_conditions(text: &str) -> Result<[bool; 256], RuleError> {
+    let error = |kind: RuleErrorKind, position: usize| RuleError::new(kind, Some(position), text);
+    let mut set = [false; 256];
+    let mut chars = text.char_indices().peekable();
+
+    while let Some((position, ch)) = chars.next() {
+        let count = match ch.to_digit(10) {
+            Some(count) if count <= 8 => count as usize,
+            _ => return Err(error(RuleErrorKind::Unexpected(ch), position)),
+        };
+
+        let negated = chars.next_if(|&(_, ch)| ch == '-');
+        let mut letters = String::new();
+        while let Some((position, letter)) = chars.next_if(|(_, c)| c.is_ascii_alphabetic()) {
+            if !LETTERS[count].contains(letter) {
+                let kind = RuleErrorKind::InvalidLetter {
+                    letter,
+                    count: count as u8,
+                };
+                return Err(error(kind, position));
+            }
+            letters.push(letter);
+        }
+
+        if letters.is_empty() {
+            if let Some((position, _)) = negated {
+                let message = format!("Expected letters after '{}-'", count);
+                return Err(error(RuleErrorKind::Invalid(message), position));
+            }
+            for pattern in 0..=255u8 {
+                if pattern.count_ones() as usize == count {
+                    set[pattern as usize] = true;
+                }
+            }
+            continue;
+        }
+
+        let selected = LETTERS[count]
+            .chars()
+            .filter(|l| letters.contains(*l) != negated.is_some());
+        for letter in selected {
+            for pattern in letter_patterns(count, letter).unwrap() {
+                set[pattern as usize] = true;
+            }
+        }
+    }
+
+    Ok(set)
+}
+
+/// Writes a set of neighbourhoods back in canonical Hensel notation, using the
+/// `-` form when more than half the letters of a count are present.
+pub fn serialize_conditions(set: &[bool; 256]) -> String {
+    let mut out = String::new();
+
+    for count in 0..=8 {
+        if LETTERS[count].is_empty() {
+            let pattern = if count == 0 { 0 } else { 0xff };
+            if set[pattern] {
+                out.push_str(&count.to_string());
+            }
+            continue;
+        }
+
+        let (present, absent): (String, String) = LETTERS[count]
+            .chars()
+            .partition(|&l| set[letter_patterns(count, l).unwrap()[0] as usize]);
+
+        if present.is_empty() {
+            continue;
+        }
+        out.push_str(&count.to_string());
+        if absent.is_empty() {
+            continue;
+        }
+        if present.len() > absent.len() {
+            out.push('-');
+            out.push_str(&absent);
+        } else {
+            out.push_str(&present);
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{Board, Rule};
+
+    #[test]
+    fn conditions_round_trip() {
+        for text in [
+            "2-a",
+            "2ce3-jr4q",
+            "012345678",
+            "1c2-a3-kqr5i8",
+            "2cek",
+            "4-wz",
+        ] {
+            let set = parse_conditions(text).unwrap();
+            assert_eq!(serialize_conditions(&set), text);
+        }
+    }
+
+    #[test]
+    fn conditions_are_written_canonically() {
+        for (text, canonical) in [
+            ("2-cek", "2ain"),
+            ("2cekain", "2"),
+            ("1ce", "1"),
+            ("3rj", "3jr"),
+        ] {
+            let set = parse_conditions(text).unwrap();
+            assert_eq!(serialize_conditions(&set), canonical, "{}", text);
+        }
+    }
+
+    #[test]
+    fn negated_letters_cover_the_rest_of_the_count() {
+        let set = parse_conditions("2-a").unwrap();
+        let pairs = (0..=255u8).filter(|p| p.count_ones() == 2);
+        // Eight of the 28 pairs of neighbours are next to each other.
+        assert_eq!(pairs.filter(|&p| set[p as usize]).count(), 20);
+        assert!(!set[0b0000_0011]);
+        assert!(set[0b0000_0101]);
+        assert!(is_isotropic(&set));
+    }
+
+    #[test]
+    fn invalid_letters_are_rejected() {
+        assert!(parse_conditions("1a").is_err());
+        assert!(parse_conditions("4z5z").is_err());
+        assert!(parse_conditions("9").is_err());
+        assert!(parse_conditions("2-").is_err());
+    }
+
+    fn run(rule: &str, cells: &[(i64, i64)]) -> Vec<(i64, i64, u8)> {
+        let rule: Rule = rule.parse().unwrap();
+        let mut board = Board::new();
+        for &(x, y) in cells {
+            board.set(x, y, true);
+        }
+        board.advance(&rule);
+        board.sorted_cells()
+    }
+
+    #[test]
+    fn letters_pick_the_births() {
+        let domino = [(0, 0), (1, 0)];
+        // Each cell beside the domino sees it as two adjacent neighbours.
+        assert_eq!(
+            run("B2/S", &domino),
+            [(0, -1, 1), (0, 1, 1), (1, -1, 1), (1, 1, 1)]
+        );
+        assert_eq!(run("B2-a/S", &domino), []);
+
+        // The cells in the corners of a diagonal pair see two orthogonal
+        // neighbours.
+        let diagonal = [(0, 0), (1, 1)];
+        assert_eq!(run("B2-a/S", &diagonal), [(0, 1, 1), (1, 0, 1)]);
+        assert_eq!(run("B2c/S", &diagonal), []);
+    }
+}
@@ -0,0 +1,152 @@
+use super::{Neighbourhood, RuleError, RuleErrorKind, CHUNK_SIZE};
+use std::ops::RangeInclusive;
+
+/// The largest supported range. Cells never look further than the chunks
+/// right next to their own.
+pub const MAX_RANGE: u8 = CHUNK_SIZE as u8;
+
+/// A Larger than Life rule, written like `R5,C0,M1,S34..58,B34..45,NM`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LargerThanLife {
+    pub range: u8,
+    /// Whether the cell itself counts towards its neighbours.
+    pub middle: bool,
+    pub neighbourhood: Neighbourhood,
+    pub survive: (u16, u16),
+    pub born: (u16, u16),
+}
+
+impl LargerThanLife {
+    /// Whether a cell is alive next generation, given the number of live
+    /// cells in its neighbourhood.
+    pub fn alive_next(&self, alive: bool, count: u16) -> bool {
+        let (min, max) = if alive { self.survive } else { self.born };
+        (min..=max).contains(&count)
+    }
+
+    /// Parses the comma separated fields of a rulestring, returning the rule
+    /// and its number of states. Errors point into `text`.
+    pub fn parse(text: &str) -> Result<(Self, u16), RuleError> {
+        let error =
+            |kind: RuleErrorKind, position: usize| RuleError::new(kind, Some(position), text);
+        let invalid =
+            |message: String, position: usize| error(RuleErrorKind::Invalid(message), position);
+        let mut rule = LargerThanLife {
+            range: 1,
+            middle: false,
+            neighbourhood: Neighbourhood::Moore,
+            survive: (2, 3),
+            born: (3, 3),
+        };
+        let mut states = 2;
+        let mut born_at = None;
+
+        let mut start = 0;
+        for field in text.split(',') {
+            let offset = start + field.len() - field.trim_start().len();
+            start += field.len() + 1;
+            let field = field.trim();
+            let Some(kind) = field.chars().next() else {
+                return Err(invalid(String::from("Empty field in rulestring"), offset));
+            };
+            let value = &field[kind.len_utf8()..];
+            let value_at = offset + kind.len_utf8();
+            let number = |value: &str, position: usize| {
+                value.parse::<u16>().map_err(|_| {
+                    invalid(
+                        format!("Invalid number '{}' in '{}'", value, field),
+                        position,
+                    )
+                })
+            };
+            let range = |value: &str| -> Result<(u16, u16), RuleError> {
+                match value.split_once("..") {
+                    Some((min, max)) => Ok((
+                        number(min, value_at)?,
+                        number(max, value_at + min.len() + 2)?,
+                    )),
+                    None => number(value, value_at).map(|n| (n, n)),
+                }
+            };
+
+            match kind.to_ascii_uppercase() {
+                'R' => match number(value, value_at)? {
+                    r @ 1.. if r <= MAX_RANGE as u16 => rule.range = r as u8,
+                    _ => return Err(invalid(format!("Range must be 1-{}", MAX_RANGE), value_at)),
+                },
+                'C' => match number(value, value_at)? {
+                    0..=2 => states = 2,
+                    c @ 3..=256 => states = c,
+                    _ => return Err(error(RuleErrorKind::StateCount, value_at)),
+                },
+                'M' => match value {
+                    "0" => rule.middle = false,
+                    "1" => rule.middle = true,
+                    _ => {
+                        return Err(invalid(
+                            format!("Expected M0 or M1, got '{}'", field),
+                            offset,
+                        ))
+                    }
+                },
+                'S' => rule.survive = range(value)?,
+                'B' => {
+                    rule.born = range(value)?;
+                    born_at = Some(value_at);
+                }
+                'N' => {
+                    rule.neighbourhood = value
+                        .chars()
+                        .next()
+                        .and_then(Neighbourhood::from_letter)
+                        .ok_or_else(|| {
+                        invalid(format!("Unknown neighbourhood '{}'", field), offset)
+                    })?
+                }
+                _ => return Err(error(RuleErrorKind::Unexpected(kind), offset)),
+            }
+        }
+
+        if rule.born.0 == 0 {
+            let message = String::from("B0 isn't supported for Larger than Life rules");
+            return Err(RuleError::new(
+                RuleErrorKind::Invalid(message),
+                born_at,
+                text,
+            ));
+        }
+
+        Ok((rule, states))
+    }
+
+    pub fn serialize(&self, states: u16) -> String {
+        let range = |(min, max): (u16, u16)| -> String {
+            if min == max {
+                min.to_string()
+            } else {
+                format!("{}..{}", min, max)
+            }
+        };
+
+        format!(
+            "R{},C{},M{},S{},B{},N{}",
+            self.range,
+            if states > 2 { states } else { 0 },
+            self.middle as u8,
+            range(self.survive),
+            range(self.born),
+            self.neighbourhood.letter()
+        )
+    }
+
+    /// Offsets of the cells in each row of the neighbourhood, by `dy`.
+    pub fn rows(&self) -> impl Iterator<Item = (i64, RangeInclusive<i64>)> + '_ {
+        let r = self.range as i64;
+        (-r..=r).map(move |dy| match self.neighbourhood {
+            Neighbourhood::Moore => (dy, -r..=r),
+            Neighbourhood::VonNeumann => (dy, -(r - dy.abs())..=r - dy.abs()),
+            // Rows above lean west and rows below lean east, see `Hexagonal`.
+            Neighbourhood::Hexagonal => (dy, (-r).max(-r - dy)..=r.min(r - dy)),
+        })
+    }
+}
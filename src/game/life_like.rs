@@ -0,0 +1,360 @@
+use rand::Rng;
+
+use super::hensel::{is_isotropic, serialize_conditions};
+use super::{Automaton, Neighbourhood, TransitionTable, MOORE};
+
+/// The bits of `LifeLike::bits` in use: survival and birth on 0-8 neighbours.
+const LIFE_LIKE_BITS: u32 = (1 << 18) - 1;
+
+/// A rule on the 3x3 neighbourhood that only looks at which neighbours are
+/// alive, including Generations rules and those written with Hensel letters
+/// or as a `MAP`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LifeLike {
+    /// Survival for each count in bits 0-8, then birth in bits 9-17.
+    bits: u32,
+    /// Number of cell states. Anything above 2 makes this a Generations rule,
+    /// where cells that die step through states `2..states` before going dead.
+    states: u16,
+    /// Birth and survival for every neighbourhood, for rules that don't only
+    /// depend on the number of live neighbours.
+    table: Option<TransitionTable>,
+    /// The neighbours `bits` counts, from a `V` or `H` suffix.
+    neighbourhood: Neighbourhood,
+}
+
+impl LifeLike {
+    /// A rule given by its whole transition table, like a `MAP` rule.
+    pub fn from_table(table: TransitionTable, states: u16) -> Self {
+        LifeLike {
+            bits: 0,
+            states,
+            table: Some(table),
+            neighbourhood: Neighbourhood::Moore,
+        }
+    }
+
+    /// Builds a rule from the sets of neighbourhoods cells are born and
+    /// survive on, falling back to the plain bits when it turns out to be
+    /// totalistic.
+    pub fn from_conditions(born: &[bool; 256], survive: &[bool; 256], states: u16) -> Self {
+        let mut bits = 0;
+        let mut totalistic = true;
+        for count in 0..=8 {
+            let of_count = (0..=255u8).filter(|p| p.count_ones() == count);
+            let b = of_count.clone().filter(|p| born[*p as usize]).count();
+            let s = of_count.clone().filter(|p| survive[*p as usize]).count();
+            let total = of_count.count();
+
+            totalistic &= (b == 0 || b == total) && (s == 0 || s == total);
+            bits |= ((b == total) as u32) << (count + 9) | ((s == total) as u32) << count;
+        }
+
+        let table = (!totalistic).then(|| {
+            TransitionTable::from_fn(|alive, pattern| {
+                if alive {
+                    survive[pattern as usize]
+                } else {
+                    born[pattern as usize]
+                }
+            })
+        });
+
+        LifeLike {
+            bits,
+            states,
+            table,
+            neighbourhood: Neighbourhood::Moore,
+        }
+    }
+
+    /// Switches a rule to the von Neumann or hexagonal neighbourhood, checking
+    /// that its counts still make sense there.
+    pub fn with_neighbourhood(self, neighbourhood: Neighbourhood) -> Result<Self, String> {
+        if self.states > 2 && self.has_b0() {
+            return Err(String::from("B0 rules can't have more than 2 states"));
+        }
+        if neighbourhood == Neighbourhood::Moore {
+            return Ok(self);
+        }
+        if self.table.is_some() {
+            return Err(String::from(
+                "Hensel letters only work with the Moore neighbourhood",
+            ));
+        }
+        if (0..=8)
+            .any(|count| count > neighbourhood.size() && (self.born(count) || self.survive(count)))
+        {
+            return Err(format!(
+                "Counts above {} don't fit the {} neighbourhood",
+                neighbourhood.size(),
+                neighbourhood
+            ));
+        }
+
+        Ok(LifeLike {
+            neighbourhood,
+            ..self
+        })
+    }
+
+    pub fn survive(&self, count: u8) -> bool {
+        (self.bits >> count) & 1 == 1
+    }
+
+    pub fn born(&self, count: u8) -> bool {
+        (self.bits >> (count + 9)) & 1 == 1
+    }
+
+    pub fn states(&self) -> u16 {
+        self.states
+    }
+
+    pub fn neighbourhood(&self) -> Neighbourhood {
+        self.neighbourhood
+    }
+
+    /// Whether the rule only depends on the number of live neighbours, in
+    /// which case `born` and `survive` describe it completely.
+    pub fn is_totalistic(&self) -> bool {
+        self.table.is_none()
+    }
+
+    /// The rule as a lookup table, which it converts into without losing
+    /// anything.
+    pub fn table(&self) -> TransitionTable {
+        self.table
+            .unwrap_or_else(|| TransitionTable::from_fn(|alive, n| self.alive_next(alive, n)))
+    }
+
+    /// Whether dead cells with no live neighbours are born.
+    pub fn has_b0(&self) -> bool {
+        self.alive_next(false, 0)
+    }
+
+    /// The rule that, when the background goes from `from` to `to` alive,
+    /// does to a board stored inverted on those generations what this rule
+    /// does to the real one.
+    pub fn emulated(&self, from: bool, to: bool) -> Self {
+        let table = TransitionTable::from_fn(|alive, pattern| {
+            let neighbours = if from { !pattern } else { pattern };
+            self.alive_next(alive != from, neighbours) != to
+        });
+
+        LifeLike {
+            table: Some(table),
+            ..*self
+        }
+    }
+
+    /// Whether a cell is alive next generation, given the pattern of its live
+    /// neighbours (NW, N, NE, W, E, SW, S, SE from the most significant bit).
+    pub fn alive_next(&self, alive: bool, neighbours: u8) -> bool {
+        match &self.table {
+            Some(table) => table.get(alive, neighbours),
+            None => {
+                let count = (neighbours & self.neighbourhood.mask()).count_ones() as u8;
+                if alive {
+                    self.survive(count)
+                } else {
+                    self.born(count)
+                }
+            }
+        }
+    }
+
+    /// The state a cell moves to given its current state and the pattern of
+    /// its live neighbours.
+    pub fn next_state(&self, state: u8, neighbours: u8) -> u8 {
+        decay(self.states, state, self.alive_next(state == 1, neighbours))
+    }
+
+    /// A random totalistic rule on the Moore neighbourhood without B0.
+    pub fn random(rng: &mut impl Rng) -> Self {
+        LifeLike {
+            bits: rng.gen::<u32>() & LIFE_LIKE_BITS & !(1 << 9),
+            states: 2,
+            table: None,
+            neighbourhood: Neighbourhood::Moore,
+        }
+    }
+
+    /// The rule with one birth or survival count flipped at random, never
+    /// B0, or `None` if it isn't totalistic.
+    pub fn mutate(&self, rng: &mut impl Rng) -> Option<Self> {
+        if !self.is_totalistic() {
+            return None;
+        }
+
+        // Survival on 0 to all neighbours, then birth on 1 to all.
+        let size = self.neighbourhood.size() as u32;
+        let bit = match rng.gen_range(0..2 * size + 1) {
+            count if count <= size => count,
+            count => count - size + 9,
+        };
+        Some(LifeLike {
+            bits: self.bits ^ (1 << bit),
+            ..*self
+        })
+    }
+
+    /// A rule taking each birth and survival count from one parent or the
+    /// other at random, or `None` unless both are totalistic with the same
+    /// neighbourhood and number of states.
+    pub fn crossover(&self, other: &LifeLike, rng: &mut impl Rng) -> Option<Self> {
+        if !self.is_totalistic()
+            || !other.is_totalistic()
+            || self.neighbourhood != other.neighbourhood
+            || self.states != other.states
+        {
+            return None;
+        }
+
+        let mask: u32 = rng.gen();
+        Some(LifeLike {
+            bits: (self.bits & mask) | (other.bits & !mask),
+            ..*self
+        })
+    }
+
+    pub fn serialize(&self) -> String {
+        let mut rulestring = match &self.table {
+            Some(table) => {
+                let set = |alive: bool| {
+                    let mut set = [false; 256];
+                    for (pattern, included) in set.iter_mut().enumerate() {
+                        *included = self.alive_next(alive, pattern as u8);
+                    }
+                    set
+                };
+                let (born, survive) = (set(false), set(true));
+
+                if is_isotropic(&born) && is_isotropic(&survive) {
+                    format!(
+                        "B{}/S{}",
+                        serialize_conditions(&born),
+                        serialize_conditions(&survive)
+                    )
+                } else {
+                    format!("MAP{}", table.to_map())
+                }
+            }
+            None => self.serialize_totalistic(),
+        };
+
+        if self.states > 2 {
+            rulestring.push_str(&format!("/{}", self.states));
+        }
+        match self.neighbourhood {
+            Neighbourhood::Moore => {}
+            Neighbourhood::VonNeumann => rulestring.push('V'),
+            Neighbourhood::Hexagonal => rulestring.push('H'),
+        }
+
+        rulestring
+    }
+
+    /// The rule as a `MAP` rulestring, however it was written.
+    pub fn serialize_map(&self) -> String {
+        let mut rulestring = format!("MAP{}", self.table().to_map());
+        if self.states > 2 {
+            rulestring.push_str(&format!("/{}", self.states));
+        }
+        rulestring
+    }
+
+    fn serialize_totalistic(&self) -> String {
+        let mut survive_str = String::new();
+        let mut born_str = String::new();
+
+        for i in 0..=8 {
+            if self.survive(i) {
+                survive_str.push_str(&i.to_string());
+            }
+        }
+
+        for i in 0..=8 {
+            if self.born(i) {
+                born_str.push_str(&i.to_string());
+            }
+        }
+
+        format!("B{}/S{}", born_str, survive_str)
+    }
+}
+
+/// The state a cell of a rule with `states` states moves to given its
+/// current state and whether the rule would have it alive next generation.
+pub(super) fn decay(states: u16, state: u8, alive_next: bool) -> u8 {
+    match state {
+        0 => alive_next as u8,
+        1 if alive_next => 1,
+        _ if (state as u16) + 1 < states => state + 1,
+        _ => 0,
+    }
+}
+
+/// Life-like rules as an automaton on the Moore neighbourhood, which is how
+/// the sequential engine runs them.
+impl Automaton for LifeLike {
+    type State = u8;
+
+    fn states(&self) -> u16 {
+        self.states
+    }
+    fn neighbourhood(&self) -> &[(i64, i64)] {
+        &MOORE
+    }
+    fn next(&self, state: u8, neighbours: &[u8]) -> u8 {
+        let pattern = neighbours.iter().fold(0, |pattern, &neighbour| {
+            (pattern << 1) | (neighbour == 1) as u8
+        });
+        self.next_state(state, pattern)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game::{Rule, RuleErrorKind};
+
+    /// Conway's Life as a `MAP` rule, as published on LifeWiki.
+    const LIFE_MAP: &str =
+        "MAPARYXfhZofugWaH7oaIDogBZofuhogOiAaIDogIAAgAAWaH7oaIDogGiA6ICAAIAAaIDogIAAgACAAIAAAAAAAA";
+
+    #[test]
+    fn life_as_a_map_is_life() {
+        let map: Rule = LIFE_MAP.parse().unwrap();
+        let life: Rule = "B3/S23".parse().unwrap();
+
+        assert_eq!(map.table(), life.table());
+        assert_eq!(map.serialize(), LIFE_MAP);
+        assert_eq!(life.life_like().unwrap().serialize_map(), LIFE_MAP);
+    }
+
+    #[test]
+    fn map_rules_keep_their_states() {
+        let text = format!("{}/3", LIFE_MAP);
+        let map: Rule = text.parse().unwrap();
+
+        assert_eq!(map.states(), 3);
+        assert_eq!(map.table(), "B3/S23/3".parse::<Rule>().unwrap().table());
+        assert_eq!(map.serialize(), text);
+    }
+
+    #[test]
+    fn bad_map_rules_point_at_the_fault() {
+        let error = |text: &str| text.parse::<Rule>().unwrap_err();
+
+        // Short of the 86 characters of 512 bits.
+        let short = &LIFE_MAP[..LIFE_MAP.len() - 4];
+        assert_eq!(error(short).position, Some(short.len()));
+
+        let mut bad = LIFE_MAP.to_string();
+        bad.replace_range(10..11, "!");
+        assert_eq!(error(&bad).position, Some(10));
+
+        let error = error(&format!("{}/300", LIFE_MAP));
+        assert_eq!(error.kind, RuleErrorKind::StateCount);
+        assert_eq!(error.position, Some(LIFE_MAP.len() + 1));
+    }
+}
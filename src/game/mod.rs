@@ -1,5 +1,50 @@
+pub use automaton::*;
+pub use block_rule::*;
 pub use board::*;
+pub use chunk::*;
+pub use classify::*;
+pub use continuous::*;
+pub use engine::*;
+pub use field::*;
+pub use larger_than_life::*;
+pub use life_like::*;
+pub use mask::*;
+pub use neighbourhood::*;
+pub use region::*;
+pub use rule_error::*;
+pub use rule_file::*;
+pub use rule_tree::*;
 pub use rulestring::*;
+pub use search::*;
+pub use stochastic::*;
+pub use topology::*;
+pub use transition_table::*;
+pub use wireworld::*;
+pub use wolfram::*;
 
+mod automaton;
+mod block_rule;
 mod board;
+mod chunk;
+mod classify;
+mod continuous;
+mod engine;
+mod fft;
+mod field;
+mod hensel;
+mod larger_than_life;
+mod life_like;
+mod mask;
+mod neighbourhood;
+mod region;
+mod rule_error;
+mod rule_file;
+mod rule_table;
+mod rule_tree;
 mod rulestring;
+mod search;
+mod stochastic;
+mod topology;
+mod transition_table;
+mod wireworld;
+mod wolfram;
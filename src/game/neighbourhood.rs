@@ -0,0 +1,154 @@
+use std::fmt;
+
+/// Which cells around a cell count as its neighbours.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Neighbourhood {
+    /// Every cell in the surrounding square.
+    Moore,
+    /// Cells within a Manhattan distance, giving a diamond.
+    VonNeumann,
+    /// Moore without the NE and SW corners, which makes a hexagonal grid when
+    /// each row is drawn shifted half a cell from the one below.
+    Hexagonal,
+}
+
+impl Neighbourhood {
+    /// The letter used for it in rulestrings, as in `NM` or `B2/S34H`.
+    pub fn letter(&self) -> char {
+        match self {
+            Neighbourhood::Moore => 'M',
+            Neighbourhood::VonNeumann => 'N',
+            Neighbourhood::Hexagonal => 'H',
+        }
+    }
+    pub fn from_letter(letter: char) -> Option<Self> {
+        match letter.to_ascii_uppercase() {
+            'M' => Some(Neighbourhood::Moore),
+            'N' | 'V' => Some(Neighbourhood::VonNeumann),
+            'H' => Some(Neighbourhood::Hexagonal),
+            _ => None,
+        }
+    }
+    /// The bits of a neighbour pattern (NW, N, NE, W, E, SW, S, SE from the
+    /// most significant bit) that are part of the neighbourhood.
+    pub fn mask(&self) -> u8 {
+        match self {
+            Neighbourhood::Moore => 0xff,
+            Neighbourhood::VonNeumann => 0x5a,
+            Neighbourhood::Hexagonal => 0xdb,
+        }
+    }
+    pub fn size(&self) -> u8 {
+        self.mask().count_ones() as u8
+    }
+}
+
+impl fmt::Display for Neighbourhood {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Neighbourhood::Moore => "Moore",
+            Neighbourhood::VonNeumann => "von Neumann",
+            Neighbourhood::Hexagonal => "hexagonal",
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{Board, Rule};
+
+    /// Offsets of the bits of a neighbour pattern, from the most significant
+    /// down.
+    const OFFSETS: [(i64, i64); 8] = [
+        (-1, 1),
+        (0, 1),
+        (1, 1),
+        (-1, 0),
+        (1, 0),
+        (-1, -1),
+        (0, -1),
+        (1, -1),
+    ];
+
+    fn offsets(neighbourhood: Neighbourhood) -> Vec<(i64, i64)> {
+        (0..8)
+            .filter(|i| neighbourhood.mask() >> (7 - i) & 1 == 1)
+            .map(|i| OFFSETS[i])
+            .collect()
+    }
+
+    #[test]
+    fn masks_pick_the_right_neighbours() {
+        assert_eq!(offsets(Neighbourhood::Moore), OFFSETS);
+        assert_eq!(
+            offsets(Neighbourhood::VonNeumann),
+            [(0, 1), (-1, 0), (1, 0), (0, -1)]
+        );
+        // Everything but NE and SW.
+        assert_eq!(Neighbourhood::Hexagonal.mask(), 0xdb);
+        assert_eq!(
+            offsets(Neighbourhood::Hexagonal),
+            [(-1, 1), (0, 1), (-1, 0), (1, 0), (0, -1), (1, -1)]
+        );
+
+        for neighbourhood in [
+            Neighbourhood::Moore,
+            Neighbourhood::VonNeumann,
+            Neighbourhood::Hexagonal,
+        ] {
+            assert_eq!(neighbourhood.size() as usize, offsets(neighbourhood).len());
+            assert_eq!(
+                Neighbourhood::from_letter(neighbourhood.letter()),
+                Some(neighbourhood)
+            );
+        }
+    }
+
+    /// The cells of a single cell after `generations` under `rule`.
+    fn grow(rule: &str, generations: usize) -> Vec<(i64, i64)> {
+        let rule: Rule = rule.parse().unwrap();
+        let mut board = Board::new();
+        board.set(0, 0, true);
+        for _ in 0..generations {
+            board.advance(&rule);
+        }
+        board
+            .sorted_cells()
+            .into_iter()
+            .map(|(x, y, _)| (x, y))
+            .collect()
+    }
+
+    fn sorted(cells: impl IntoIterator<Item = (i64, i64)>) -> Vec<(i64, i64)> {
+        let mut cells: Vec<_> = cells.into_iter().collect();
+        cells.sort_unstable();
+        cells
+    }
+
+    #[test]
+    fn single_cells_grow_into_their_neighbourhood() {
+        for (rule, neighbourhood) in [
+            ("B1/S", Neighbourhood::Moore),
+            ("B1/SV", Neighbourhood::VonNeumann),
+            ("B1/SH", Neighbourhood::Hexagonal),
+        ] {
+            assert_eq!(grow(rule, 1), sorted(offsets(neighbourhood)), "{}", rule);
+        }
+    }
+
+    #[test]
+    fn rings_only_grow_from_their_corners() {
+        // Only the cells straight out from a ring of neighbours have one of
+        // them as a neighbour, the rest have two or more.
+        for (rule, neighbourhood) in [
+            ("B1/SV", Neighbourhood::VonNeumann),
+            ("B1/SH", Neighbourhood::Hexagonal),
+        ] {
+            let expected = offsets(neighbourhood)
+                .into_iter()
+                .map(|(dx, dy)| (2 * dx, 2 * dy));
+            assert_eq!(grow(rule, 2), sorted(expected), "{}", rule);
+        }
+    }
+}
@@ -0,0 +1,39 @@
+use super::Rule;
+use serde::{Deserialize, Serialize};
+
+/// A rectangle of the board that runs under its own rule. Cells on its edge
+/// are inside it, and look at their neighbours outside it like any others.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct Region {
+    /// The lowest corner, inclusive.
+    pub min: (i64, i64),
+    /// The highest corner, inclusive.
+    pub max: (i64, i64),
+    pub rule: Rule,
+}
+
+impl Region {
+    pub fn contains(&self, x: i64, y: i64) -> bool {
+        (self.min.0..=self.max.0).contains(&x) && (self.min.1..=self.max.1).contains(&y)
+    }
+    pub fn overlaps(&self, min: (i64, i64), max: (i64, i64)) -> bool {
+        self.min.0 <= max.0 && min.0 <= self.max.0 && self.min.1 <= max.1 && min.1 <= self.max.1
+    }
+}
+
+/// Why `rule` can't share a board with other rules, if it can't.
+pub fn region_error(rule: &Rule) -> Option<String> {
+    if rule.blocks().is_some() || rule.wolfram().is_some() {
+        return Some(format!(
+            "{}: Block rules and 1D rules can't share the board with other rules",
+            rule.serialize()
+        ));
+    }
+    if rule.has_b0() {
+        return Some(format!(
+            "{}: B0 rules can't share the board with other rules",
+            rule.serialize()
+        ));
+    }
+    None
+}
@@ -0,0 +1,102 @@
+use super::Neighbourhood;
+use std::fmt;
+
+/// Why a rulestring couldn't be parsed, and where.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RuleError {
+    pub kind: RuleErrorKind,
+    /// The byte offset of the character at fault, when it's down to one.
+    pub position: Option<usize>,
+    pub rulestring: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RuleErrorKind {
+    /// A character that doesn't belong where it is.
+    Unexpected(char),
+    /// A part of the rule that's missing, like the `S` of `B3/23`.
+    Expected(char),
+    /// A neighbour count that's more than the neighbourhood holds.
+    CountTooHigh {
+        count: u8,
+        neighbourhood: Neighbourhood,
+    },
+    /// A Hensel letter the count before it doesn't have.
+    InvalidLetter { letter: char, count: u8 },
+    /// Hensel letters on the von Neumann or hexagonal neighbourhood.
+    LettersNeedMoore,
+    /// A Generations state count outside 2-256.
+    StateCount,
+    /// Something that isn't any kind of rulestring.
+    Format,
+    /// Whatever the parsers of the other kinds of rules make of it.
+    Invalid(String),
+}
+
+impl RuleError {
+    pub fn new(kind: RuleErrorKind, position: Option<usize>, rulestring: &str) -> Self {
+        RuleError {
+            kind,
+            position,
+            rulestring: rulestring.to_string(),
+        }
+    }
+    /// The error as one in a longer rulestring, where the text it was found
+    /// in starts at byte `offset`.
+    pub fn within(self, offset: usize, rulestring: &str) -> Self {
+        RuleError::new(
+            self.kind,
+            self.position.map(|position| position + offset),
+            rulestring,
+        )
+    }
+}
+
+impl fmt::Display for RuleErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RuleErrorKind::Unexpected(ch) => write!(f, "Unexpected '{}'", ch),
+            RuleErrorKind::Expected(ch) => write!(f, "Expected '{}'", ch),
+            RuleErrorKind::CountTooHigh {
+                count,
+                neighbourhood,
+            } => write!(
+                f,
+                "{} is more neighbours than the {} neighbourhood has ({})",
+                count,
+                neighbourhood,
+                neighbourhood.size()
+            ),
+            RuleErrorKind::InvalidLetter { letter, count } => {
+                write!(f, "'{}' is not a valid letter for {}", letter, count)
+            }
+            RuleErrorKind::LettersNeedMoore => {
+                f.write_str("Hensel letters only work with the Moore neighbourhood")
+            }
+            RuleErrorKind::StateCount => f.write_str("State count must be 2-256"),
+            RuleErrorKind::Format => {
+                f.write_str("Expected birth and survival conditions, like B3/S23")
+            }
+            RuleErrorKind::Invalid(message) => f.write_str(message),
+        }
+    }
+}
+
+impl fmt::Display for RuleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.position {
+            Some(position) => write!(
+                f,
+                "{} at character {} of '{}'",
+                self.kind,
+                position + 1,
+                self.rulestring
+            ),
+            // Messages from the parsers of other kinds of rules stand alone.
+            None if matches!(self.kind, RuleErrorKind::Invalid(_)) => write!(f, "{}", self.kind),
+            None => write!(f, "{} in '{}'", self.kind, self.rulestring),
+        }
+    }
+}
+
+impl std::error::Error for RuleError {}
@@ -0,0 +1,186 @@
+use super::{rule_table, Neighbourhood, RuleTree};
+use crate::utils::BASE_DIR;
+use std::fs;
+
+/// A rule from a Golly `.rule` file in the `rules` directory, made of an
+/// `@TABLE` or `@TREE` section and optionally `@COLORS`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RuleFile {
+    pub name: String,
+    pub tree: RuleTree,
+    /// The neighbourhood of the `@TABLE`, so hexagonal rules are drawn as such.
+    pub neighbourhood: Neighbourhood,
+    /// Colours from `@COLORS`, by state.
+    pub colors: Vec<Option<[u8; 3]>>,
+}
+
+impl RuleFile {
+    pub fn load(name: &str) -> Result<Self, String> {
+        let path = rule_path(name);
+        let text = fs::read_to_string(&path).map_err(|_| {
+            format!(
+                "Unknown rule '{}': it isn't a rulestring and there's no {}",
+                name, path
+            )
+        })?;
+
+        Self::parse(name, &text).map_err(|err| format!("{}: {}", path, err))
+    }
+
+    pub fn parse(name: &str, text: &str) -> Result<Self, String> {
+        // The body of each section, by its header line.
+        let mut sections: Vec<(&str, String)> = Vec::new();
+        for line in text.lines() {
+            if line.starts_with('@') {
+                sections.push((line.trim(), String::new()));
+            } else if let Some((_, body)) = sections.last_mut() {
+                body.push_str(line);
+                body.push('\n');
+            }
+        }
+        let section = |header: &str| {
+            sections
+                .iter()
+                .find(|(line, _)| line.split_whitespace().next() == Some(header))
+                .map(|(_, body)| body.as_str())
+        };
+
+        let declared = sections
+            .iter()
+            .find_map(|(line, _)| line.strip_prefix("@RULE"))
+            .map(str::trim)
+            .ok_or("Missing @RULE")?;
+        if declared != name {
+            return Err(format!("The file describes rule '{}'", declared));
+        }
+
+        // Golly prefers the table when a file has both.
+        let (tree, neighbourhood) = match (section("@TABLE"), section("@TREE")) {
+            (Some(table), _) => rule_table::parse(table)?,
+            (None, Some(tree)) => (RuleTree::parse(tree)?, Neighbourhood::Moore),
+            (None, None) => return Err(String::from("Missing @TABLE or @TREE")),
+        };
+        if tree.next_state(|_, _| 0) != 0 {
+            return Err(String::from(
+                "Rules where empty space comes alive aren't supported",
+            ));
+        }
+
+        let colors = match section("@COLORS") {
+            Some(colors) => parse_colors(colors, tree.states())?,
+            None => vec![None; tree.states() as usize],
+        };
+
+        Ok(RuleFile {
+            name: name.to_string(),
+            tree,
+            neighbourhood,
+            colors,
+        })
+    }
+}
+
+pub fn rule_path(name: &str) -> String {
+    format!("{}/rules/{}.rule", *BASE_DIR, name)
+}
+
+/// Parses lines of `state r g b`, or `r1 g1 b1 r2 g2 b2` for a gradient over
+/// every state but 0.
+fn parse_colors(text: &str, states: u16) -> Result<Vec<Option<[u8; 3]>>, String> {
+    let mut colors = vec![None; states as usize];
+
+    for line in text.lines() {
+        let line = line.split('#').next().unwrap().trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let numbers = line
+            .split_whitespace()
+            .map(|n| n.parse::<u8>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| format!("Invalid colour '{}'", line))?;
+        match numbers[..] {
+            [state, r, g, b] => {
+                let color = colors
+                    .get_mut(state as usize)
+                    .ok_or_else(|| format!("State {} is out of range", state))?;
+                *color = Some([r, g, b]);
+            }
+            [r1, g1, b1, r2, g2, b2] => {
+                for (state, color) in colors.iter_mut().enumerate().skip(1) {
+                    let t = (state - 1) as f32 / (states as f32 - 2.).max(1.);
+                    let lerp = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+                    *color = Some([lerp(r1, r2), lerp(g1, g2), lerp(b1, b2)]);
+                }
+            }
+            _ => return Err(format!("Invalid colour '{}'", line)),
+        }
+    }
+
+    Ok(colors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Rule;
+
+    /// Three live neighbours bring a cell to life, which then dies into state 2.
+    const TABLE: &str = "n_states:3\nneighborhood:Moore\nsymmetries:permute\n0,1,1,1,0,0,0,0,0,1\n1,0,0,0,0,0,0,0,0,2\n";
+
+    fn rule_file(colors: &str) -> Result<RuleFile, String> {
+        RuleFile::parse(
+            "Test",
+            &format!("@RULE Test\n\n@TABLE\n{}\n@COLORS\n{}", TABLE, colors),
+        )
+    }
+
+    #[test]
+    fn colors_are_read_by_state() {
+        let file = rule_file("1 255 0 0\n2 0 0 255  # blue").unwrap();
+        assert_eq!(file.colors, [None, Some([255, 0, 0]), Some([0, 0, 255])]);
+    }
+
+    #[test]
+    fn colors_can_be_a_gradient() {
+        let file = rule_file("0 0 0 200 100 50").unwrap();
+        assert_eq!(file.colors, [None, Some([0, 0, 0]), Some([200, 100, 50])]);
+    }
+
+    #[test]
+    fn invalid_colors_are_rejected() {
+        assert!(rule_file("3 255 0 0").unwrap_err().contains("State 3"));
+        assert!(rule_file("1 255 0").unwrap_err().contains("Invalid colour"));
+        assert!(rule_file("1 256 0 0")
+            .unwrap_err()
+            .contains("Invalid colour"));
+    }
+
+    #[test]
+    fn files_need_a_matching_rule_and_a_body() {
+        let error = |text: &str| RuleFile::parse("Test", text).unwrap_err();
+
+        assert_eq!(error("@TABLE\n"), "Missing @RULE");
+        assert_eq!(
+            error("@RULE Other\n@TABLE\n"),
+            "The file describes rule 'Other'"
+        );
+        assert_eq!(error("@RULE Test\n@COLORS\n"), "Missing @TABLE or @TREE");
+        assert!(error("@RULE Test\n@TABLE\nn_states:2\nneighborhood:Moore\nsymmetries:none\n0,0,0,0,0,0,0,0,0,1\n")
+            .contains("empty space comes alive"));
+    }
+
+    #[test]
+    fn unknown_rules_say_where_the_file_should_be() {
+        let name = "NoSuchRuleFile";
+        let path = rule_path(name);
+
+        let error = RuleFile::load(name).unwrap_err();
+        assert!(error.starts_with("Unknown rule 'NoSuchRuleFile'"));
+        assert!(error.ends_with(&path));
+
+        let error = name.parse::<Rule>().unwrap_err().to_string();
+        assert!(error.starts_with("Unknown rule 'NoSuchRuleFile'"));
+    }
+}
@@ -0,0 +1,551 @@
+use super::rule_tree::{TreeBuilder, C, E, N, NE, NW, S, SE, SW, W};
+use super::{Neighbourhood, RuleTree};
+use std::collections::{HashMap, HashSet};
+
+/// The neighbourhoods of Golly's `@TABLE` format.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Shape {
+    VonNeumann,
+    Moore,
+    Hexagonal,
+    OneDimensional,
+}
+
+impl Shape {
+    fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "vonneumann" => Some(Shape::VonNeumann),
+            "moore" => Some(Shape::Moore),
+            "hexagonal" => Some(Shape::Hexagonal),
+            "onedimensional" => Some(Shape::OneDimensional),
+            _ => None,
+        }
+    }
+
+    /// The neighbours in the order transitions list them, going clockwise.
+    fn neighbours(self) -> &'static [(i64, i64)] {
+        match self {
+            Shape::VonNeumann => &[N, E, S, W],
+            Shape::Moore => &[N, NE, E, SE, S, SW, W, NW],
+            Shape::Hexagonal => &[N, E, SE, S, W, NW],
+            Shape::OneDimensional => &[W, E],
+        }
+    }
+
+    /// The rotation step and whether to reflect for each symmetry Golly
+    /// allows on this neighbourhood. Reflections keep the first neighbour in
+    /// place and reverse the rest.
+    fn symmetry(self, name: &str) -> Option<(usize, bool)> {
+        let size = self.neighbours().len();
+        match (self, name) {
+            (_, "none") => Some((size, false)),
+            (Shape::Moore | Shape::VonNeumann, "reflect_horizontal") => Some((size, true)),
+            (Shape::Moore, "rotate4") => Some((2, false)),
+            (Shape::Moore, "rotate4reflect") => Some((2, true)),
+            (Shape::Moore, "rotate8") => Some((1, false)),
+            (Shape::Moore, "rotate8reflect") => Some((1, true)),
+            (Shape::VonNeumann, "rotate4") => Some((1, false)),
+            (Shape::VonNeumann, "rotate4reflect") => Some((1, true)),
+            (Shape::Hexagonal, "rotate2") => Some((3, false)),
+            (Shape::Hexagonal, "rotate3") => Some((2, false)),
+            (Shape::Hexagonal, "rotate6") => Some((1, false)),
+            (Shape::Hexagonal, "rotate6reflect") => Some((1, true)),
+            // Swapping the two neighbours is the only thing to do in 1D.
+            (Shape::OneDimensional, "reflect") => Some((1, false)),
+            _ => None,
+        }
+    }
+
+    /// Where each neighbour is taken from in every variant of a transition.
+    fn permutations(self, (step, reflect): (usize, bool)) -> Vec<Vec<usize>> {
+        let size = self.neighbours().len();
+        let mut permutations = Vec::new();
+        for rotation in (0..size).step_by(step) {
+            for reflected in [false, true] {
+                if reflected && !reflect {
+                    continue;
+                }
+                permutations.push(
+                    (0..size)
+                        .map(|i| if reflected { (size - i) % size } else { i })
+                        .map(|i| (i + rotation) % size)
+                        .collect(),
+                );
+            }
+        }
+        permutations
+    }
+}
+
+/// A set of cell states, one bit per state.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+struct StateSet([u64; 4]);
+
+impl StateSet {
+    fn single(state: u8) -> Self {
+        let mut set = StateSet([0; 4]);
+        set.0[state as usize / 64] |= 1 << (state % 64);
+        set
+    }
+    fn union(self, other: Self) -> Self {
+        StateSet([0, 1, 2, 3].map(|i| self.0[i] | other.0[i]))
+    }
+    fn states(self) -> impl Iterator<Item = u8> {
+        (0..4).flat_map(move |i| {
+            let mut bits = self.0[i];
+            std::iter::from_fn(move || {
+                if bits == 0 {
+                    return None;
+                }
+                let bit = bits.trailing_zeros();
+                bits &= bits - 1;
+                Some((i as u32 * 64 + bit) as u8)
+            })
+        })
+    }
+}
+
+/// One line of the table after variables and symmetries are expanded, with
+/// the cell itself first and then its neighbours, like in the table.
+struct Transition {
+    cells: Vec<StateSet>,
+    output: u8,
+    /// The first cell after which the transition matches any state.
+    settled: usize,
+}
+
+/// Compiles the body of an `@TABLE` section into a tree, also returning the
+/// neighbourhood it's drawn with.
+pub fn parse(text: &str) -> Result<(RuleTree, Neighbourhood), String> {
+    let mut states: Option<u16> = None;
+    let mut shape = None;
+    let mut symmetry = None;
+    let mut variables: HashMap<String, StateSet> = HashMap::new();
+    let mut transitions = Vec::new();
+    let mut seen = HashSet::new();
+
+    for line in text.lines() {
+        let line = line.split('#').next().unwrap().trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        if let Some(definition) = line.strip_prefix("var ") {
+            let states = states.ok_or("n_states must come before any variable")?;
+            let (name, values) = definition
+                .split_once('=')
+                .ok_or_else(|| format!("Invalid variable '{}'", line))?;
+            let values = values
+                .trim()
+                .strip_prefix('{')
+                .and_then(|values| values.strip_suffix('}'))
+                .ok_or_else(|| format!("Invalid variable '{}'", line))?;
+
+            let mut set = StateSet([0; 4]);
+            for value in values.split(',') {
+                set = set.union(parse_value(value.trim(), states, &variables)?);
+            }
+            variables.insert(name.trim().to_string(), set);
+            continue;
+        }
+
+        if let Some((key, value)) = line.split_once(':') {
+            let value = value.trim();
+            match key.trim() {
+                "n_states" => match value.parse::<u16>() {
+                    Ok(n @ 2..=256) => states = Some(n),
+                    _ => return Err(String::from("State count must be 2-256")),
+                },
+                "neighborhood" => {
+                    shape = Some(
+                        Shape::from_name(value)
+                            .ok_or_else(|| format!("Unknown neighborhood '{}'", value))?,
+                    )
+                }
+                "symmetries" => symmetry = Some(value.to_string()),
+                key => return Err(format!("Unknown key '{}' in @TABLE", key)),
+            }
+            continue;
+        }
+
+        let (Some(states), Some(shape), Some(symmetry)) = (states, shape, &symmetry) else {
+            return Err(String::from(
+                "@TABLE needs n_states, neighborhood and symmetries before its transitions",
+            ));
+        };
+
+        // Tables with up to 10 states can leave out the commas.
+        let tokens: Vec<&str> = if line.contains(',') {
+            line.split(',').map(str::trim).collect()
+        } else {
+            line.char_indices()
+                .map(|(i, c)| &line[i..i + c.len_utf8()])
+                .collect()
+        };
+        if tokens.len() != shape.neighbours().len() + 2 {
+            return Err(format!("Wrong number of states in '{}'", line));
+        }
+
+        let permutations = match symmetry.as_str() {
+            "permute" => None,
+            name => Some(shape.permutations(shape.symmetry(name).ok_or_else(|| {
+                format!("Symmetry '{}' doesn't work with this neighborhood", name)
+            })?)),
+        };
+
+        for (cells, output) in expand(&tokens, states, &variables)? {
+            let (centre, neighbours) = cells.split_first().unwrap();
+            let variants: Vec<Vec<StateSet>> = match &permutations {
+                Some(permutations) => permutations
+                    .iter()
+                    .map(|permutation| permutation.iter().map(|&i| neighbours[i]).collect())
+                    .collect(),
+                None => distinct_permutations(neighbours),
+            };
+
+            for variant in variants {
+                let variant: Vec<StateSet> = [*centre].into_iter().chain(variant).collect();
+                // Later duplicates could never match.
+                if seen.insert(variant.clone()) {
+                    let settled = variant
+                        .iter()
+                        .rposition(|set| set.states().count() < states as usize)
+                        .map_or(0, |i| i + 1);
+                    transitions.push(Transition {
+                        cells: variant,
+                        output,
+                        settled,
+                    });
+                }
+            }
+        }
+    }
+
+    let (Some(states), Some(shape)) = (states, shape) else {
+        return Err(String::from("@TABLE is missing n_states or neighborhood"));
+    };
+
+    // Branching on the cell itself first splits the transitions early, as
+    // most tables start with a different set of lines for each state.
+    let inputs: Vec<_> = [C].iter().chain(shape.neighbours()).copied().collect();
+    let mut compiler = Compiler {
+        transitions: &transitions,
+        depth: inputs.len(),
+        builder: TreeBuilder::new(states),
+        known: HashMap::new(),
+    };
+    let root = compiler.root();
+
+    let neighbourhood = match shape {
+        Shape::VonNeumann => Neighbourhood::VonNeumann,
+        Shape::Hexagonal => Neighbourhood::Hexagonal,
+        Shape::Moore | Shape::OneDimensional => Neighbourhood::Moore,
+    };
+    Ok((compiler.builder.build(inputs, root), neighbourhood))
+}
+
+/// A state or the states of a variable.
+fn parse_value(
+    value: &str,
+    states: u16,
+    variables: &HashMap<String, StateSet>,
+) -> Result<StateSet, String> {
+    if let Some(set) = variables.get(value) {
+        return Ok(*set);
+    }
+    match value.parse::<u16>() {
+        Ok(state) if state < states => Ok(StateSet::single(state as u8)),
+        Ok(state) => Err(format!("State {} is out of range", state)),
+        Err(_) => Err(format!("Unknown variable '{}'", value)),
+    }
+}
+
+/// Expands the variables of a transition. Like Golly, a variable used more
+/// than once is bound: it stands for the same state everywhere in the line.
+fn expand(
+    tokens: &[&str],
+    states: u16,
+    variables: &HashMap<String, StateSet>,
+) -> Result<Vec<(Vec<StateSet>, u8)>, String> {
+    let bound: Vec<&str> = tokens
+        .iter()
+        .copied()
+        .filter(|token| variables.contains_key(*token))
+        .filter(|token| tokens.iter().filter(|t| *t == token).count() > 1)
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
+
+    let mut assignments: Vec<HashMap<&str, u8>> = vec![HashMap::new()];
+    for name in bound {
+        assignments = assignments
+            .into_iter()
+            .flat_map(|assignment| {
+                variables[name].states().map(move |state| {
+                    let mut assignment = assignment.clone();
+                    assignment.insert(name, state);
+                    assignment
+                })
+            })
+            .collect();
+    }
+
+    let (output, inputs) = tokens.split_last().unwrap();
+    assignments
+        .into_iter()
+        .map(|assignment| {
+            let cells = inputs
+                .iter()
+                .map(|token| match assignment.get(token) {
+                    Some(&state) => Ok(StateSet::single(state)),
+                    None => parse_value(token, states, variables),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+
+            let output = match assignment.get(output) {
+                Some(&state) => state,
+                None if variables.contains_key(*output) => {
+                    return Err(format!(
+                        "Variable '{}' has to appear among the inputs to be an output",
+                        output
+                    ))
+                }
+                None => match output.parse::<u16>() {
+                    Ok(state) if state < states => state as u8,
+                    _ => return Err(format!("Invalid output '{}'", output)),
+                },
+            };
+            Ok((cells, output))
+        })
+        .collect()
+}
+
+/// Every distinct ordering of the neighbours, for the `permute` symmetry.
+fn distinct_permutations(neighbours: &[StateSet]) -> Vec<Vec<StateSet>> {
+    let mut current = neighbours.to_vec();
+    current.sort();
+    let mut permutations = vec![current.clone()];
+
+    // Steps through the orderings lexicographically, which skips repeats.
+    loop {
+        let Some(i) = (1..current.len())
+            .rev()
+            .find(|&i| current[i - 1] < current[i])
+        else {
+            return permutations;
+        };
+        let j = (i..current.len())
+            .rev()
+            .find(|&j| current[i - 1] < current[j])
+            .unwrap();
+        current.swap(i - 1, j);
+        current[i..].reverse();
+        permutations.push(current.clone());
+    }
+}
+
+/// Turns the transitions into a tree, one level per cell. Subtrees only
+/// depend on the state of the cell itself and on which transitions can still
+/// match, so those are shared.
+struct Compiler<'a> {
+    transitions: &'a [Transition],
+    depth: usize,
+    builder: TreeBuilder,
+    known: HashMap<(usize, u8, Vec<u32>), u32>,
+}
+
+impl Compiler<'_> {
+    fn root(&mut self) -> u32 {
+        let all = (0..self.transitions.len() as u32).collect::<Vec<_>>();
+        let children = self
+            .split(0, &all)
+            .into_iter()
+            .enumerate()
+            .map(|(state, matching)| self.node(1, state as u8, matching))
+            .collect();
+        self.builder.node(children)
+    }
+
+    /// The candidates that can still match for each state of the cell on
+    /// `level`.
+    fn split(&self, level: usize, candidates: &[u32]) -> Vec<Vec<u32>> {
+        let mut matching = vec![Vec::new(); self.builder.states() as usize];
+        for &t in candidates {
+            for state in self.transitions[t as usize].cells[level].states() {
+                matching[state as usize].push(t);
+            }
+        }
+        matching
+    }
+
+    fn node(&mut self, level: usize, centre: u8, mut candidates: Vec<u32>) -> u32 {
+        // Nothing after a transition that matches whatever is left can match.
+        if let Some(i) = candidates
+            .iter()
+            .position(|&t| self.transitions[t as usize].settled <= level)
+        {
+            candidates.truncate(i + 1);
+        }
+
+        let key = (level, centre, candidates);
+        if let Some(&node) = self.known.get(&key) {
+            return node;
+        }
+
+        let children = self
+            .split(level, &key.2)
+            .into_iter()
+            .map(|matching| {
+                if level + 1 == self.depth {
+                    // Cells stay as they are when no transition matches.
+                    matching
+                        .first()
+                        .map_or(centre, |&t| self.transitions[t as usize].output)
+                        as u32
+                } else {
+                    self.node(level + 1, centre, matching)
+                }
+            })
+            .collect();
+
+        let node = self.builder.node(children);
+        self.known.insert(key, node);
+        node
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(shape: &str, symmetries: &str, states: u16, transitions: &str) -> RuleTree {
+        let text = format!(
+            "n_states:{}\nneighborhood:{}\nsymmetries:{}\n{}",
+            states, shape, symmetries, transitions
+        );
+        parse(&text).unwrap().0
+    }
+
+    /// How many of the patterns of live neighbours bring a dead cell to life.
+    fn births(tree: &RuleTree, shape: Shape) -> usize {
+        let neighbours = shape.neighbours();
+        (0..1u32 << neighbours.len())
+            .filter(|pattern| {
+                let next = tree.next_state(|dx, dy| {
+                    neighbours
+                        .iter()
+                        .position(|&offset| offset == (dx, dy))
+                        .map_or(0, |i| (pattern >> i & 1) as u8)
+                });
+                next == 1
+            })
+            .count()
+    }
+
+    #[test]
+    fn variables_used_twice_are_bound() {
+        // The north and east neighbours have to be in the same state, the
+        // south and west ones don't.
+        let tree = table(
+            "vonNeumann",
+            "none",
+            3,
+            "var a={1,2}\nvar b={1,2}\nvar c={1,2}\n0,a,a,0,0,1\n0,0,0,b,c,2",
+        );
+        let next = |n: u8, e: u8, s: u8, w: u8| {
+            tree.next_state(|dx, dy| match (dx, dy) {
+                N => n,
+                E => e,
+                S => s,
+                W => w,
+                _ => 0,
+            })
+        };
+
+        assert_eq!(next(1, 1, 0, 0), 1);
+        assert_eq!(next(2, 2, 0, 0), 1);
+        assert_eq!(next(1, 2, 0, 0), 0);
+        assert_eq!(next(0, 0, 1, 2), 2);
+        assert_eq!(next(0, 0, 2, 1), 2);
+    }
+
+    #[test]
+    fn outputs_need_bound_variables() {
+        let text = "n_states:3\nneighborhood:vonNeumann\nsymmetries:none\nvar a={1,2}\n0,a,0,0,0,a";
+        assert!(parse(text).is_ok());
+        let text = "n_states:3\nneighborhood:vonNeumann\nsymmetries:none\nvar a={1,2}\n0,1,0,0,0,a";
+        assert!(parse(text)
+            .unwrap_err()
+            .contains("has to appear among the inputs"));
+    }
+
+    #[test]
+    fn symmetries_match_every_variant_of_a_transition() {
+        // Two neighbours next to each other, the first one on top.
+        let moore = "0,1,1,0,0,0,0,0,0,1";
+        for (symmetries, expected) in [
+            ("none", 1),
+            ("reflect_horizontal", 2),
+            ("rotate4", 4),
+            ("rotate4reflect", 8),
+            ("rotate8", 8),
+            ("rotate8reflect", 8),
+            ("permute", 28),
+        ] {
+            let tree = table("Moore", symmetries, 2, moore);
+            assert_eq!(births(&tree, Shape::Moore), expected, "{}", symmetries);
+        }
+
+        let von_neumann = "0,1,1,0,0,1";
+        for (symmetries, expected) in [
+            ("none", 1),
+            ("reflect_horizontal", 2),
+            ("rotate4", 4),
+            ("rotate4reflect", 4),
+            ("permute", 6),
+        ] {
+            let tree = table("vonNeumann", symmetries, 2, von_neumann);
+            assert_eq!(births(&tree, Shape::VonNeumann), expected, "{}", symmetries);
+        }
+
+        let hexagonal = "0,1,1,0,0,0,0,1";
+        for (symmetries, expected) in [
+            ("none", 1),
+            ("rotate2", 2),
+            ("rotate3", 3),
+            ("rotate6", 6),
+            ("rotate6reflect", 6),
+            ("permute", 15),
+        ] {
+            let tree = table("hexagonal", symmetries, 2, hexagonal);
+            assert_eq!(births(&tree, Shape::Hexagonal), expected, "{}", symmetries);
+        }
+
+        for (symmetries, expected) in [("none", 1), ("reflect", 2), ("permute", 2)] {
+            let tree = table("oneDimensional", symmetries, 2, "0,1,0,1");
+            assert_eq!(
+                births(&tree, Shape::OneDimensional),
+                expected,
+                "{}",
+                symmetries
+            );
+        }
+    }
+
+    #[test]
+    fn symmetries_have_to_fit_the_neighbourhood() {
+        let text = "n_states:2\nneighborhood:hexagonal\nsymmetries:rotate4\n0,1,1,0,0,0,0,1";
+        assert!(parse(text).unwrap_err().contains("rotate4"));
+    }
+
+    #[test]
+    fn earlier_transitions_win() {
+        let tree = table(
+            "Moore",
+            "permute",
+            2,
+            "0,1,1,1,0,0,0,0,0,1\n0,1,1,1,0,0,0,0,0,0",
+        );
+        assert_eq!(births(&tree, Shape::Moore), 56);
+    }
+}
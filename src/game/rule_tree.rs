@@ -0,0 +1,281 @@
+use std::collections::HashMap;
+
+/// A rule as a decision tree over the states of the cells around a cell, the
+/// form Golly compiles every `.rule` file into.
+///
+/// Each level of the tree branches on the state of one cell, in the order of
+/// `inputs`, and the bottom level holds the state the cell moves to.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RuleTree {
+    states: u16,
+    /// Offsets of the cells the tree branches on, from the root down.
+    inputs: Vec<(i64, i64)>,
+    /// `states` entries per node: the index of the child for inner nodes and
+    /// the next state on the bottom level.
+    nodes: Vec<u32>,
+    root: u32,
+}
+
+pub(super) const NW: (i64, i64) = (-1, 1);
+pub(super) const N: (i64, i64) = (0, 1);
+pub(super) const NE: (i64, i64) = (1, 1);
+pub(super) const W: (i64, i64) = (-1, 0);
+pub(super) const C: (i64, i64) = (0, 0);
+pub(super) const E: (i64, i64) = (1, 0);
+pub(super) const SW: (i64, i64) = (-1, -1);
+pub(super) const S: (i64, i64) = (0, -1);
+pub(super) const SE: (i64, i64) = (1, -1);
+
+impl RuleTree {
+    pub fn states(&self) -> u16 {
+        self.states
+    }
+
+    /// The next state of a cell, given the state of the cell at each offset
+    /// from it. States the rule doesn't have count as dead.
+    pub fn next_state(&self, state_at: impl Fn(i64, i64) -> u8) -> u8 {
+        let mut node = self.root;
+        for &(dx, dy) in &self.inputs {
+            let state = state_at(dx, dy) as usize;
+            let state = if state < self.states as usize {
+                state
+            } else {
+                0
+            };
+            node = self.nodes[node as usize + state];
+        }
+        node as u8
+    }
+
+    /// Builds the tree of a rule on the Moore neighbourhood from the next
+    /// state of a cell, given its state and those of its neighbours (NW, N, NE,
+    /// W, E, SW, S, SE).
+    pub fn from_fn(states: u16, f: impl Fn(u8, [u8; 8]) -> u8) -> Self {
+        fn node(
+            builder: &mut TreeBuilder,
+            cells: &mut Vec<u8>,
+            f: &impl Fn(u8, [u8; 8]) -> u8,
+        ) -> u32 {
+            let children = (0..builder.states() as u8)
+                .map(|state| {
+                    cells.push(state);
+                    let child = match cells[..] {
+                        [centre, nw, n, ne, w, e, sw, s, se] => {
+                            f(centre, [nw, n, ne, w, e, sw, s, se]) as u32
+                        }
+                        _ => node(builder, cells, f),
+                    };
+                    cells.pop();
+                    child
+                })
+                .collect();
+            builder.node(children)
+        }
+
+        let mut builder = TreeBuilder::new(states);
+        let root = node(&mut builder, &mut Vec::new(), &f);
+        builder.build(vec![C, NW, N, NE, W, E, SW, S, SE], root)
+    }
+
+    /// Parses the body of an `@TREE` section.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut states = None;
+        let mut neighbours = None;
+        let mut builder = None;
+        // Index and level of each node line.
+        let mut lines: Vec<(u32, usize)> = Vec::new();
+
+        for line in text.lines() {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            if let Some((key, value)) = line.split_once('=') {
+                let value = value
+                    .trim()
+                    .parse::<usize>()
+                    .map_err(|_| format!("Invalid number in '{}'", line))?;
+                match key.trim() {
+                    "num_states" if (2..=256).contains(&value) => states = Some(value as u16),
+                    "num_states" => return Err(String::from("State count must be 2-256")),
+                    "num_neighbors" if value == 4 || value == 8 => neighbours = Some(value),
+                    "num_neighbors" => return Err(String::from("Trees need 4 or 8 neighbours")),
+                    "num_nodes" => {}
+                    key => return Err(format!("Unknown key '{}' in @TREE", key)),
+                }
+                continue;
+            }
+
+            let (Some(states), Some(neighbours)) = (states, neighbours) else {
+                return Err(String::from(
+                    "@TREE needs num_states and num_neighbors before its nodes",
+                ));
+            };
+            let builder = builder.get_or_insert_with(|| TreeBuilder::new(states));
+
+            let numbers = line
+                .split_whitespace()
+                .map(|n| n.parse::<usize>())
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|_| format!("Invalid node '{}'", line))?;
+            let (&level, children) = numbers
+                .split_first()
+                .ok_or_else(|| format!("Invalid node '{}'", line))?;
+            if children.len() != states as usize || level == 0 || level > neighbours + 1 {
+                return Err(format!("Invalid node '{}'", line));
+            }
+
+            let children = children
+                .iter()
+                .map(|&child| match level {
+                    1 if child < states as usize => Ok(child as u32),
+                    1 => Err(format!("Invalid state {} in node '{}'", child, line)),
+                    _ => match lines.get(child) {
+                        Some(&(index, child_level)) if child_level == level - 1 => Ok(index),
+                        _ => Err(format!("Invalid child {} in node '{}'", child, line)),
+                    },
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            lines.push((builder.node(children), level));
+        }
+
+        let neighbours = neighbours.ok_or("@TREE is missing num_neighbors")?;
+        let (Some(builder), Some(&(root, level))) = (builder, lines.last()) else {
+            return Err(String::from("@TREE has no nodes"));
+        };
+        if level != neighbours + 1 {
+            return Err(String::from("The last node of @TREE must be the root"));
+        }
+
+        let inputs = match neighbours {
+            4 => vec![N, W, E, S, C],
+            _ => vec![NW, NE, SW, SE, N, W, E, S, C],
+        };
+        Ok(builder.build(inputs, root))
+    }
+}
+
+/// Builds the nodes of a [`RuleTree`] bottom up, sharing identical nodes.
+pub(super) struct TreeBuilder {
+    states: u16,
+    nodes: Vec<u32>,
+    known: HashMap<Vec<u32>, u32>,
+}
+
+impl TreeBuilder {
+    pub fn new(states: u16) -> Self {
+        TreeBuilder {
+            states,
+            nodes: Vec::new(),
+            known: HashMap::new(),
+        }
+    }
+
+    pub fn states(&self) -> u16 {
+        self.states
+    }
+
+    /// Adds a node with a child for every state, returning its index.
+    pub fn node(&mut self, children: Vec<u32>) -> u32 {
+        debug_assert_eq!(children.len(), self.states as usize);
+        if let Some(&index) = self.known.get(&children) {
+            return index;
+        }
+
+        let index = self.nodes.len() as u32;
+        self.nodes.extend_from_slice(&children);
+        self.known.insert(children, index);
+        index
+    }
+
+    pub fn build(self, inputs: Vec<(i64, i64)>, root: u32) -> RuleTree {
+        RuleTree {
+            states: self.states,
+            inputs,
+            nodes: self.nodes,
+            root,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every cell takes the state of the one north of it, so patterns move
+    /// south.
+    const FALLING: &str = "num_states=2
+num_neighbors=4
+num_nodes=9
+1 0 0
+1 1 1
+2 0 0
+2 1 1
+3 2 2
+3 3 3
+4 4 4
+4 5 5
+5 6 7
+";
+
+    #[test]
+    fn trees_give_their_transitions() {
+        let tree = RuleTree::parse(FALLING).unwrap();
+        assert_eq!(tree.states(), 2);
+
+        for n in 0..2 {
+            for others in 0..16u8 {
+                let next = tree.next_state(|dx, dy| match (dx, dy) {
+                    N => n,
+                    W => others & 1,
+                    E => others >> 1 & 1,
+                    S => others >> 2 & 1,
+                    C => others >> 3 & 1,
+                    _ => 1,
+                });
+                assert_eq!(next, n);
+            }
+        }
+    }
+
+    #[test]
+    fn states_the_tree_doesnt_have_count_as_dead() {
+        let tree = RuleTree::parse(FALLING).unwrap();
+        assert_eq!(tree.next_state(|_, _| 5), 0);
+    }
+
+    #[test]
+    fn trees_from_functions_see_the_right_neighbours() {
+        let life = RuleTree::from_fn(2, |centre, neighbours| {
+            let count = neighbours.iter().sum::<u8>();
+            (count == 3 || centre == 1 && count == 2) as u8
+        });
+        let glider = [N, E, SW, S, SE];
+        let alive = |cells: &[(i64, i64)], (x, y): (i64, i64)| {
+            life.next_state(|dx, dy| cells.contains(&(x + dx, y + dy)) as u8)
+        };
+        assert_eq!(alive(&glider, C), 0);
+        assert_eq!(alive(&glider, E), 1);
+        assert_eq!(alive(&glider, (0, -2)), 1);
+        assert_eq!(alive(&glider, SW), 0);
+
+        // The neighbours come in the order the function is documented with.
+        for (i, offset) in [NW, N, NE, W, E, SW, S, SE].into_iter().enumerate() {
+            let tree = RuleTree::from_fn(2, |_, neighbours| neighbours[i]);
+            assert_eq!(tree.next_state(|dx, dy| ((dx, dy) == offset) as u8), 1);
+            assert_eq!(tree.next_state(|dx, dy| ((dx, dy) != offset) as u8), 0);
+        }
+    }
+
+    #[test]
+    fn invalid_trees_are_rejected() {
+        let error = |text: &str| RuleTree::parse(text).unwrap_err();
+
+        assert!(error("num_states=2\nnum_neighbors=6\n").contains("4 or 8"));
+        assert!(error("1 0 0\n").contains("before its nodes"));
+        assert!(error("num_states=2\nnum_neighbors=4\n1 0 2\n").contains("Invalid state 2"));
+        assert!(error("num_states=2\nnum_neighbors=4\n1 0 0\n3 0 0\n").contains("Invalid child"));
+        assert!(error("num_states=2\nnum_neighbors=4\n1 0 0\n2 0 0\n").contains("root"));
+    }
+}
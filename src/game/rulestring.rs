@@ -1,47 +1,335 @@
+use rand::Rng;
 use serde::de::{self, Visitor};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Arc;
 
-pub struct Rule(u32);
+use super::hensel::{is_letter_of, parse_conditions};
+use super::{
+    parse_chances, registered, rule_path, BlockRule, DynAutomaton, LargerThanLife, LifeLike,
+    NamedAutomaton, Neighbourhood, RuleError, RuleErrorKind, RuleFile, RuleTree, Stochastic,
+    Topology, TransitionTable, Wolfram, WIREWORLD, WIREWORLD_TREE,
+};
 
-#[derive(Deserialize)]
-struct RuleHolder {
-    rule: Rule,
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Rule {
+    kind: RuleKind,
+    /// Set when the rulestring has a Golly-style suffix such as `:T120,80`.
+    pub topology: Option<Topology>,
+}
+
+/// The kinds of rule a rulestring can describe.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RuleKind {
+    /// Birth and survival on the live neighbours of a cell, like `B3/S23`,
+    /// `B2-a/S12`, `B2/S/3` or `B2/S34H`.
+    LifeLike(LifeLike),
+    /// A life-like rule given as its whole transition table, like `MAP...`.
+    Map(LifeLike),
+    /// Larger than Life, and its number of states.
+    LtL(LargerThanLife, u16),
+    /// A rule loaded from a Golly `.rule` file.
+    File(Arc<RuleFile>),
+    /// Built-in WireWorld, which doesn't need a rule file.
+    WireWorld,
+    /// A Margolus block rule.
+    Block(BlockRule),
+    /// A one dimensional rule.
+    Wolfram(Wolfram),
+    /// A chance of birth and survival for each count of live neighbours.
+    Stochastic {
+        stochastic: Stochastic,
+        states: u16,
+        neighbourhood: Neighbourhood,
+    },
+    /// An automaton registered by name.
+    Registered(NamedAutomaton),
+}
+
+/// Characters in the base64 table of a `MAP` rule, without padding.
+const MAP_LENGTH: usize = 86;
+
+/// Well known rules by name, matched ignoring case, spaces and punctuation.
+const ALIASES: [(&str, &str); 20] = [
+    ("life", "B3/S23"),
+    ("highlife", "B36/S23"),
+    ("seeds", "B2/S"),
+    ("daynight", "B3678/S34678"),
+    ("maze", "B3/S12345"),
+    ("mazectric", "B3/S1234"),
+    ("replicator", "B1357/S1357"),
+    ("2x2", "B36/S125"),
+    ("34life", "B34/S34"),
+    ("diamoeba", "B35678/S5678"),
+    ("morley", "B368/S245"),
+    ("move", "B368/S245"),
+    ("anneal", "B4678/S35678"),
+    ("coral", "B3/S45678"),
+    ("gnarl", "B1/S1"),
+    ("longlife", "B345/S5"),
+    ("lifewithoutdeath", "B3/S012345678"),
+    ("briansbrain", "B2/S/3"),
+    ("starwars", "B2/S345/4"),
+    ("bosco", "R5,C2,M1,S34..58,B34..45,NM"),
+];
+
+/// The rulestring of a well known rule called `name`.
+fn alias(name: &str) -> Option<&'static str> {
+    let name: String = name
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|ch| ch.to_ascii_lowercase())
+        .collect();
+    ALIASES
+        .iter()
+        .find(|(alias, _)| *alias == name)
+        .map(|(_, rulestring)| *rulestring)
 }
 
 impl Rule {
-    pub fn survive(&self, count: u8) -> bool {
-        (self.0 >> count) & 1 == 1
+    pub fn kind(&self) -> &RuleKind {
+        &self.kind
     }
 
-    pub fn born(&self, count: u8) -> bool {
-        (self.0 >> (count + 8)) & 1 == 1
+    pub fn states(&self) -> u16 {
+        match &self.kind {
+            RuleKind::LifeLike(rule) | RuleKind::Map(rule) => rule.states(),
+            RuleKind::LtL(_, states) | RuleKind::Stochastic { states, .. } => *states,
+            RuleKind::File(file) => file.tree.states(),
+            RuleKind::WireWorld => 4,
+            RuleKind::Block(_) | RuleKind::Wolfram(_) => 2,
+            RuleKind::Registered(named) => named.automaton.states(),
+        }
     }
 
-    pub fn from_str(rulestring: &str) -> Self {
-        let rule_holder: RuleHolder =
-            toml::from_str(&format!("rule = \"{}\"", rulestring)).unwrap();
+    /// The rule as a life-like one, for rules that only look at which of the
+    /// 3x3 neighbours are alive.
+    pub fn life_like(&self) -> Option<&LifeLike> {
+        match &self.kind {
+            RuleKind::LifeLike(rule) | RuleKind::Map(rule) => Some(rule),
+            _ => None,
+        }
+    }
 
-        rule_holder.rule
+    /// Whether the rule only depends on the number of live neighbours.
+    pub fn is_totalistic(&self) -> bool {
+        self.life_like().is_some_and(LifeLike::is_totalistic)
     }
 
-    pub fn serialize(&self) -> String {
-        let mut survive_str = String::new();
-        let mut born_str = String::new();
+    /// The rule as a lookup table, which every life-like rule converts into
+    /// without losing anything. `None` for every other kind of rule.
+    pub fn table(&self) -> Option<TransitionTable> {
+        self.life_like().map(LifeLike::table)
+    }
 
-        for i in 0..8 {
-            if self.survive(i) {
-                survive_str.push_str(&i.to_string());
-            }
+    pub fn larger_than_life(&self) -> Option<&LargerThanLife> {
+        match &self.kind {
+            RuleKind::LtL(ltl, _) => Some(ltl),
+            _ => None,
+        }
+    }
+
+    /// The decision tree of WireWorld and rule files.
+    pub fn tree(&self) -> Option<&RuleTree> {
+        match &self.kind {
+            RuleKind::File(file) => Some(&file.tree),
+            RuleKind::WireWorld => Some(&WIREWORLD_TREE),
+            _ => None,
+        }
+    }
+
+    pub fn blocks(&self) -> Option<&BlockRule> {
+        match &self.kind {
+            RuleKind::Block(blocks) => Some(blocks),
+            _ => None,
+        }
+    }
+
+    pub fn wolfram(&self) -> Option<&Wolfram> {
+        match &self.kind {
+            RuleKind::Wolfram(wolfram) => Some(wolfram),
+            _ => None,
+        }
+    }
+
+    pub fn stochastic(&self) -> Option<&Stochastic> {
+        match &self.kind {
+            RuleKind::Stochastic { stochastic, .. } => Some(stochastic),
+            _ => None,
+        }
+    }
+
+    /// The automaton registered under the rule's name, if it names one.
+    pub fn registered(&self) -> Option<&NamedAutomaton> {
+        match &self.kind {
+            RuleKind::Registered(named) => Some(named),
+            _ => None,
+        }
+    }
+
+    /// The rule as an [`Automaton`]: the registered one, or the life-like
+    /// rule. `None` for every other kind of rule.
+    pub fn automaton(&self) -> Option<&dyn DynAutomaton> {
+        match &self.kind {
+            RuleKind::Registered(named) => Some(&*named.automaton),
+            RuleKind::LifeLike(rule) | RuleKind::Map(rule) => Some(rule),
+            _ => None,
+        }
+    }
+
+    pub fn is_wireworld(&self) -> bool {
+        self.kind == RuleKind::WireWorld
+    }
+
+    /// The colour a rule file or registered automaton gives `state`, if any.
+    pub fn color(&self, state: u8) -> Option<[u8; 3]> {
+        match &self.kind {
+            RuleKind::Registered(named) => named.automaton.color(state),
+            RuleKind::File(file) => file.colors.get(state as usize).copied().flatten(),
+            _ => None,
+        }
+    }
+
+    pub fn neighbourhood(&self) -> Neighbourhood {
+        match &self.kind {
+            RuleKind::LifeLike(rule) | RuleKind::Map(rule) => rule.neighbourhood(),
+            RuleKind::LtL(ltl, _) => ltl.neighbourhood,
+            RuleKind::File(file) => file.neighbourhood,
+            RuleKind::Stochastic { neighbourhood, .. } => *neighbourhood,
+            _ => Neighbourhood::Moore,
+        }
+    }
+
+    /// How far away cells can affect each other in one generation.
+    pub fn range(&self) -> u8 {
+        match &self.kind {
+            RuleKind::Registered(named) => named.reach,
+            RuleKind::LtL(ltl, _) => ltl.range,
+            _ => 1,
+        }
+    }
+
+    /// Whether dead cells with no live neighbours are born, or empty blocks
+    /// fill up or empty rows come alive, which would switch on the whole
+    /// infinite plane.
+    pub fn has_b0(&self) -> bool {
+        match &self.kind {
+            RuleKind::LifeLike(rule) | RuleKind::Map(rule) => rule.has_b0(),
+            RuleKind::Block(blocks) => blocks.strobes(),
+            RuleKind::Wolfram(wolfram) => wolfram.has_b0(),
+            _ => false,
+        }
+    }
+
+    /// Whether the infinite background is alive on `generation`. Like Golly,
+    /// boards store those generations inverted, so B0 rules never have to
+    /// fill the plane and don't strobe. Bounded planes have no background.
+    pub fn background_alive(&self, generation: u64, topology: Topology) -> bool {
+        if !self.has_b0() || matches!(topology, Topology::Bounded { .. }) {
+            return false;
         }
 
-        for i in 0..8 {
-            if self.born(i) {
-                born_str.push_str(&i.to_string());
+        let keeps_full = match &self.kind {
+            RuleKind::LifeLike(rule) | RuleKind::Map(rule) => rule.alive_next(true, 0xff),
+            RuleKind::Wolfram(wolfram) => wolfram.keeps_full(),
+            _ => false,
+        };
+        if keeps_full {
+            generation > 0
+        } else {
+            generation % 2 == 1
+        }
+    }
+
+    /// The rule that takes the board as stored on `generation` to how it is
+    /// stored on the next one. See [`Rule::background_alive`].
+    pub fn emulated(&self, generation: u64, topology: Topology) -> Rule {
+        let from = self.background_alive(generation, topology);
+        let to = self.background_alive(generation + 1, topology);
+        if !from && !to {
+            return self.clone();
+        }
+
+        let kind = match &self.kind {
+            RuleKind::LifeLike(rule) => RuleKind::LifeLike(rule.emulated(from, to)),
+            RuleKind::Map(rule) => RuleKind::Map(rule.emulated(from, to)),
+            RuleKind::Block(blocks) => RuleKind::Block(blocks.emulated(from, to)),
+            RuleKind::Wolfram(wolfram) => RuleKind::Wolfram(wolfram.emulated(from, to)),
+            _ => unreachable!("only life-like, block and 1D rules have B0"),
+        };
+        Rule {
+            kind,
+            topology: self.topology,
+        }
+    }
+
+    pub fn serialize(&self) -> String {
+        let mut rulestring = match &self.kind {
+            RuleKind::LifeLike(rule) => rule.serialize(),
+            RuleKind::Map(rule) => rule.serialize_map(),
+            RuleKind::LtL(ltl, states) => ltl.serialize(*states),
+            RuleKind::File(file) => file.name.clone(),
+            RuleKind::WireWorld => WIREWORLD.to_string(),
+            RuleKind::Block(blocks) => blocks.serialize(),
+            RuleKind::Wolfram(wolfram) => wolfram.serialize(),
+            RuleKind::Stochastic {
+                stochastic,
+                states,
+                neighbourhood,
+            } => {
+                let mut rulestring = stochastic.serialize();
+                if *states > 2 {
+                    rulestring.push_str(&format!("/{}", states));
+                }
+                match neighbourhood {
+                    Neighbourhood::Moore => {}
+                    Neighbourhood::VonNeumann => rulestring.push('V'),
+                    Neighbourhood::Hexagonal => rulestring.push('H'),
+                }
+                rulestring
             }
+            RuleKind::Registered(named) => named.name.clone(),
+        };
+        if let Some(topology) = self.topology {
+            rulestring.push_str(&format!(":{}", topology.serialize()));
+        }
+
+        rulestring
+    }
+
+    /// A random life-like rule without B0, for the rule explorer.
+    pub fn random(rng: &mut impl Rng) -> Self {
+        Rule {
+            kind: RuleKind::LifeLike(LifeLike::random(rng)),
+            topology: None,
+        }
+    }
+
+    /// The rule with one birth or survival count flipped at random, never
+    /// B0, or `None` if it isn't totalistic.
+    pub fn mutate(&self, rng: &mut impl Rng) -> Option<Self> {
+        match &self.kind {
+            RuleKind::LifeLike(rule) => Some(Rule {
+                kind: RuleKind::LifeLike(rule.mutate(rng)?),
+                topology: self.topology,
+            }),
+            _ => None,
         }
+    }
 
-        format!("{}/{}", survive_str, born_str)
+    /// A rule taking each birth and survival count from one parent or the
+    /// other at random, or `None` unless both are totalistic rules with the
+    /// same neighbourhood and number of states.
+    pub fn crossover(&self, other: &Rule, rng: &mut impl Rng) -> Option<Self> {
+        match (&self.kind, &other.kind) {
+            (RuleKind::LifeLike(rule), RuleKind::LifeLike(other)) => Some(Rule {
+                kind: RuleKind::LifeLike(rule.crossover(other, rng)?),
+                topology: self.topology,
+            }),
+            _ => None,
+        }
     }
 }
 
@@ -65,51 +353,524 @@ impl<'de> Deserialize<'de> for Rule {
             type Value = Rule;
 
             fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-                formatter.write_str("a cellular automata rulestring, like '23/3'")
+                formatter.write_str("a cellular automata rulestring, like 'B3/S23', '23/3', 'B2/S/3', 'B2-a/S12', 'B2/S34H', 'MAP...', 'R5,C0,M1,S34..58,B34..45,NM', 'MS,D0;8;4;3;2;5;9;7;1;6;10;11;12;13;14;15', 'W30', 'T20R2', 'B36(0.1)/S23', 'B3/S23:T120,80', a name like 'HighLife', or the name of a rule file or of a registered automaton")
             }
 
             fn visit_str<E>(self, value: &str) -> Result<Rule, E>
             where
                 E: de::Error,
             {
-                let parts: Vec<&str> = value.split('/').collect();
-                if parts.len() != 2 {
-                    return Err(de::Error::custom("Invalid rulestring format"));
+                value.parse().map_err(de::Error::custom)
+            }
+        }
+
+        deserializer.deserialize_str(RuleVisitor)
+    }
+}
+
+impl FromStr for Rule {
+    type Err = RuleError;
+
+    fn from_str(rulestring: &str) -> Result<Self, RuleError> {
+        let invalid =
+            |message: String| RuleError::new(RuleErrorKind::Invalid(message), None, rulestring);
+        let error = |kind: RuleErrorKind, position: Option<usize>| {
+            RuleError::new(kind, position, rulestring)
+        };
+
+        let (value, topology) = match rulestring.split_once(':') {
+            Some((value, suffix)) => {
+                let topology = suffix
+                    .parse()
+                    .map_err(|err: RuleError| err.within(value.len() + 1, rulestring))?;
+                (value, Some(topology))
+            }
+            None => (rulestring, None),
+        };
+
+        // Like Golly, anything that can't be a rulestring names a built-in
+        // rule, a registered automaton or a `.rule` file, except that Larger
+        // than Life gets a go at names starting with R when there's no such
+        // file.
+        let is_name =
+            !value.contains(['/', ',']) && !value.starts_with("MAP") && !is_slashless(value);
+        if let Some(alias) = alias(value).filter(|_| is_name) {
+            let rule: Rule = alias.parse().expect("aliases are valid rulestrings");
+            return Ok(Rule { topology, ..rule });
+        }
+        let blocks = match value.strip_prefix("MS,D") {
+            Some(blocks) => Some(BlockRule::parse(blocks).map_err(invalid)?),
+            None if is_name => BlockRule::preset(value),
+            None => None,
+        };
+        if let Some(blocks) = blocks {
+            return Ok(Rule {
+                kind: RuleKind::Block(blocks),
+                topology,
+            });
+        }
+        if is_name && value.eq_ignore_ascii_case(WIREWORLD) {
+            return Ok(Rule {
+                kind: RuleKind::WireWorld,
+                topology,
+            });
+        }
+        if is_name && Wolfram::matches(value) {
+            let wolfram = Wolfram::parse(value).map_err(invalid)?;
+            return Ok(Rule {
+                kind: RuleKind::Wolfram(wolfram),
+                topology,
+            });
+        }
+        if let Some(automaton) = registered(value).filter(|_| is_name) {
+            return Ok(Rule {
+                kind: RuleKind::Registered(automaton),
+                topology,
+            });
+        }
+        if is_name && (!value.starts_with(['R', 'r']) || Path::new(&rule_path(value)).is_file()) {
+            let file = RuleFile::load(value).map_err(invalid)?;
+            return Ok(Rule {
+                kind: RuleKind::File(Arc::new(file)),
+                topology,
+            });
+        }
+
+        if value.starts_with(['R', 'r']) {
+            let (ltl, states) =
+                LargerThanLife::parse(value).map_err(|err| err.within(0, rulestring))?;
+            return Ok(Rule {
+                kind: RuleKind::LtL(ltl, states),
+                topology,
+            });
+        }
+
+        if let Some(map) = value.strip_prefix("MAP") {
+            // Base64 can contain '/', so the state count comes after the
+            // fixed length table.
+            if let Some((position, ch)) = map.char_indices().find(|(_, ch)| !ch.is_ascii()) {
+                return Err(error(RuleErrorKind::Unexpected(ch), Some(position + 3)));
+            }
+            let (map, rest) = map.split_at(map.len().min(MAP_LENGTH));
+            let states = match rest.trim_start_matches('=') {
+                "" => None,
+                rest => Some(rest.strip_prefix('/').unwrap_or(rest)),
+            };
+            let states = match states.map(|s| s.parse::<u16>()) {
+                None => 2,
+                Some(Ok(states)) if (2..=256).contains(&states) => states,
+                Some(_) => {
+                    let position = value.len() - states.unwrap_or_default().len();
+                    return Err(error(RuleErrorKind::StateCount, Some(position)));
                 }
+            };
+            let table = TransitionTable::from_map(map).map_err(|err| err.within(3, rulestring))?;
 
-                let survive_part = parts[0];
-                let born_part = parts[1];
+            let rule = LifeLike::from_table(table, states);
+            if rule.states() > 2 && rule.has_b0() {
+                return Err(invalid(String::from(
+                    "B0 rules can't have more than 2 states",
+                )));
+            }
+            return Ok(Rule {
+                kind: RuleKind::Map(rule),
+                topology,
+            });
+        }
 
-                let mut rule_value: u32 = 0;
+        let (value, neighbourhood) = match value.strip_suffix(['V', 'v', 'H', 'h']) {
+            Some(rest) => (
+                rest,
+                Neighbourhood::from_letter(value.chars().last().unwrap()).unwrap(),
+            ),
+            None => (value, Neighbourhood::Moore),
+        };
 
-                for ch in survive_part.chars() {
-                    if let Some(digit) = ch.to_digit(10) {
-                        if digit < 8 {
-                            rule_value |= 1 << digit;
-                        } else {
-                            return Err(de::Error::custom("Survival count out of range"));
-                        }
-                    } else {
-                        return Err(de::Error::custom("Invalid character in survival string"));
+        let (born, survive, states) =
+            split_conditions(value).map_err(|(kind, position)| error(kind, position))?;
+        let states = match states {
+            Some((text, position)) => {
+                match text.trim_start_matches(['C', 'c', 'G', 'g']).parse::<u16>() {
+                    Ok(states) if (2..=256).contains(&states) => states,
+                    _ => return Err(error(RuleErrorKind::StateCount, Some(position))),
+                }
+            }
+            None => 2,
+        };
+
+        if value.contains('(') {
+            let chances = |(text, offset): Part| {
+                parse_chances(text, neighbourhood.size())
+                    .map_err(|err| err.within(offset, rulestring))
+            };
+            let stochastic = Stochastic::new(chances(born)?, chances(survive)?)
+                .map_err(|message| error(RuleErrorKind::Invalid(message), Some(born.1)))?;
+            return Ok(Rule {
+                kind: RuleKind::Stochastic {
+                    stochastic,
+                    states,
+                    neighbourhood,
+                },
+                topology,
+            });
+        }
+
+        // Hensel letters need the B and S to tell them apart from counts.
+        let letters = value.starts_with(['B', 'b', 'S', 's']);
+        for (text, offset) in [born, survive] {
+            check_conditions(text, neighbourhood, letters)
+                .map_err(|(kind, position)| error(kind, Some(offset + position)))?;
+        }
+        let (born, survive) = (
+            parse_conditions(born.0).map_err(|err| err.within(born.1, rulestring))?,
+            parse_conditions(survive.0).map_err(|err| err.within(survive.1, rulestring))?,
+        );
+        let rule = LifeLike::from_conditions(&born, &survive, states)
+            .with_neighbourhood(neighbourhood)
+            .map_err(invalid)?;
+        Ok(Rule {
+            kind: RuleKind::LifeLike(rule),
+            topology,
+        })
+    }
+}
+
+/// A part of a rulestring and its byte offset in it.
+type Part<'a> = (&'a str, usize);
+
+/// The birth and survival conditions of a rule, and its state count if given.
+type Conditions<'a> = (Part<'a>, Part<'a>, Option<Part<'a>>);
+
+/// Whether a rule like `B3S23` leaves out the slash.
+fn is_slashless(value: &str) -> bool {
+    let mut chars = value.chars();
+    match (chars.next(), chars.next()) {
+        (Some(first), Some(second)) => {
+            let other = match first.to_ascii_uppercase() {
+                'B' => 'S',
+                'S' => 'B',
+                _ => return false,
+            };
+            second.is_ascii_digit() || second.to_ascii_uppercase() == other
+        }
+        _ => false,
+    }
+}
+
+/// Splits a life-like rule into its birth conditions, survival conditions
+/// and state count, accepting `B3/S23`, `S23/B3`, `B3S23`, and `23/3` with
+/// survival first. Errors come with the offset of the character at fault.
+fn split_conditions(value: &str) -> Result<Conditions<'_>, (RuleErrorKind, Option<usize>)> {
+    let mut parts: Vec<Part> = Vec::new();
+    let mut start = 0;
+    for (position, _) in value.match_indices('/') {
+        parts.push((&value[start..position], start));
+        start = position + 1;
+    }
+    parts.push((&value[start..], start));
+
+    let letter = |(text, _): Part| text.chars().next().map(|ch| ch.to_ascii_uppercase());
+    let lettered = |part: Part| matches!(letter(part), Some('B' | 'S'));
+    fn strip((text, offset): Part) -> Part {
+        (&text[1..], offset + 1)
+    }
+
+    let (first, second) = match parts[..] {
+        [part] if lettered(part) => {
+            // `B3S23`, split where the second letter starts.
+            let other = if letter(part) == Some('B') { 'S' } else { 'B' };
+            let split = part
+                .0
+                .find([other, other.to_ascii_lowercase()])
+                .ok_or((RuleErrorKind::Expected(other), Some(value.len())))?;
+            ((&part.0[..split], 0), (&part.0[split..], split))
+        }
+        [_] => return Err((RuleErrorKind::Format, None)),
+        [first, second] | [first, second, _] => (first, second),
+        [_, _, _, (_, offset), ..] => {
+            return Err((RuleErrorKind::Unexpected('/'), Some(offset - 1)))
+        }
+        [] => unreachable!("splitting always gives at least one part"),
+    };
+    let states = parts.get(2).copied();
+
+    if !lettered(first) && !lettered(second) {
+        return Ok((second, first, states));
+    }
+    for part in [first, second] {
+        if !lettered(part) {
+            let other = if letter(first) == Some('B') || letter(second) == Some('B') {
+                'S'
+            } else {
+                'B'
+            };
+            return Err((RuleErrorKind::Expected(other), Some(part.1)));
+        }
+    }
+    match (letter(first), letter(second)) {
+        (Some('B'), Some('S')) => Ok((strip(first), strip(second), states)),
+        (Some('S'), Some('B')) => Ok((strip(second), strip(first), states)),
+        (_, Some(ch)) => Err((RuleErrorKind::Unexpected(ch), Some(second.1))),
+        _ => unreachable!("both parts are lettered"),
+    }
+}
+
+/// Checks conditions like `3` or `2-a4i` one character at a time, so that
+/// errors can point at the one at fault. Hensel letters are only allowed when
+/// `letters` is set.
+fn check_conditions(
+    text: &str,
+    neighbourhood: Neighbourhood,
+    letters: bool,
+) -> Result<(), (RuleErrorKind, usize)> {
+    let mut count = None;
+    let mut chars = text.char_indices().peekable();
+
+    while let Some((position, ch)) = chars.next() {
+        match ch {
+            '0'..='9' => {
+                let digit = ch.to_digit(10).unwrap() as u8;
+                if digit > neighbourhood.size() {
+                    return Err((
+                        RuleErrorKind::CountTooHigh {
+                            count: digit,
+                            neighbourhood,
+                        },
+                        position,
+                    ));
+                }
+                count = Some(digit);
+            }
+            '-' if letters && count.is_some() => {
+                if !chars
+                    .peek()
+                    .is_some_and(|(_, next)| next.is_ascii_alphabetic())
+                {
+                    return Err((RuleErrorKind::Unexpected('-'), position));
+                }
+            }
+            _ if letters && ch.is_ascii_alphabetic() && count.is_some() => {
+                if neighbourhood != Neighbourhood::Moore {
+                    return Err((RuleErrorKind::LettersNeedMoore, position));
+                }
+                let count = count.unwrap();
+                if !is_letter_of(count, ch) {
+                    return Err((RuleErrorKind::InvalidLetter { letter: ch, count }, position));
+                }
+            }
+            _ => return Err((RuleErrorKind::Unexpected(ch), position)),
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{Board, Sequential};
+    use std::collections::HashSet;
+
+    #[test]
+    fn every_form_of_life_is_the_same_rule() {
+        let life: Rule = "B3/S23".parse().unwrap();
+        for text in ["S23/B3", "23/3", "B3S23", "b3/s23", "s23/b3", "B3/S32"] {
+            assert_eq!(text.parse::<Rule>().unwrap(), life, "{}", text);
+        }
+        assert_eq!(life.serialize(), "B3/S23");
+    }
+
+    #[test]
+    fn counts_go_up_to_eight() {
+        let survive: Rule = "B3/S238".parse().unwrap();
+        assert!(survive.life_like().unwrap().survive(8));
+        assert!(!survive.life_like().unwrap().born(8));
+        assert_eq!(survive.serialize(), "B3/S238");
+
+        let born: Rule = "B38/S23".parse().unwrap();
+        assert!(born.life_like().unwrap().born(8));
+        assert_eq!(born, "23/38".parse().unwrap());
+        assert_eq!(born.serialize(), "B38/S23");
+    }
+
+    #[test]
+    fn aliases_are_their_rules() {
+        for (name, rulestring) in ALIASES {
+            let rule: Rule = name.parse().unwrap();
+            assert_eq!(rule, rulestring.parse().unwrap(), "{}", name);
+            assert_eq!(rule.serialize().parse::<Rule>().unwrap(), rule, "{}", name);
+        }
+        let day_and_night: Rule = "Day & Night".parse().unwrap();
+        assert_eq!(day_and_night.serialize(), "B3678/S34678");
+        let high_life: Rule = "HIGHLIFE".parse().unwrap();
+        assert_eq!(high_life.serialize(), "B36/S23");
+    }
+
+    #[test]
+    fn rules_serialize_in_b_s_form() {
+        for (text, canonical) in [
+            ("23/36", "B36/S23"),
+            ("S/B2", "B2/S"),
+            ("B0/S8", "B0/S8"),
+            ("s1357/b1357", "B1357/S1357"),
+            ("B2/S/3", "B2/S/3"),
+            ("Seeds", "B2/S"),
+        ] {
+            let rule: Rule = text.parse().unwrap();
+            assert_eq!(rule.serialize(), canonical, "{}", text);
+            assert_eq!(canonical.parse::<Rule>().unwrap(), rule, "{}", text);
+        }
+    }
+
+    #[test]
+    fn errors_in_other_kinds_of_rules_point_at_the_character() {
+        let position = |text: &str| text.parse::<Rule>().unwrap_err().position;
+
+        assert_eq!(position("B3(2)/S23"), Some(3));
+        assert_eq!(position("B36(0.1)/S2(0.5"), Some(15));
+        assert_eq!(position("R5,C0,M1,S34..x8,B34..45,NM"), Some(14));
+        assert_eq!(position("R5,C0,M1,S34..58,B34..45,NQ"), Some(25));
+        assert_eq!(position("R5,C0,M1,S34..58,B0..45,NM"), Some(18));
+        let map = format!("MAP{}!{}", "A".repeat(5), "A".repeat(80));
+        assert_eq!(position(&map), Some(8));
+        assert_eq!(position("MAPAAAA"), Some(7));
+    }
+
+    #[test]
+    fn larger_than_life_fields_can_start_with_any_character() {
+        let error = "R5,é".parse::<Rule>().unwrap_err();
+        assert_eq!(error.kind, RuleErrorKind::Unexpected('é'));
+        assert_eq!(error.position, Some(3));
+
+        let error = "R5,C0,Sé".parse::<Rule>().unwrap_err();
+        assert_eq!(error.position, Some(7));
+    }
+
+    #[test]
+    fn hensel_errors_point_at_the_character() {
+        let error = |text: &str| parse_conditions(text).unwrap_err();
+
+        assert_eq!(error("2-").position, Some(1));
+        assert_eq!(
+            error("3ax").kind,
+            RuleErrorKind::InvalidLetter {
+                letter: 'x',
+                count: 3
+            }
+        );
+        assert_eq!(error("3ax").position, Some(2));
+        assert_eq!(error("29").position, Some(1));
+    }
+
+    #[test]
+    fn bosco_is_an_alias() {
+        let bosco: Rule = "Bosco".parse().unwrap();
+        assert_eq!(bosco, "R5,C2,M1,S34..58,B34..45,NM".parse().unwrap());
+        assert!(bosco.larger_than_life().is_some());
+    }
+
+    /// Runs a life-like rule cell by cell on `window`, without storing any
+    /// generation inverted. Cells outside the window are dead on bounded
+    /// topologies, and the returned background everywhere on the plane.
+    fn run_directly(
+        rule: &LifeLike,
+        topology: Topology,
+        window: ((i64, i64), (i64, i64)),
+        mut live: HashSet<(i64, i64)>,
+        generations: u64,
+    ) -> (HashSet<(i64, i64)>, bool) {
+        let ((x0, y0), (x1, y1)) = window;
+        let mut background = false;
+        for _ in 0..generations {
+            let alive = |x: i64, y: i64| match topology {
+                Topology::Plane if x < x0 || x > x1 || y < y0 || y > y1 => background,
+                _ => topology
+                    .resolve(x, y)
+                    .is_some_and(|cell| live.contains(&cell)),
+            };
+            let mut next = HashSet::new();
+            for y in y0..=y1 {
+                for x in x0..=x1 {
+                    let count = (-1..=1)
+                        .flat_map(|dy| (-1..=1).map(move |dx| (dx, dy)))
+                        .filter(|&(dx, dy)| (dx, dy) != (0, 0) && alive(x + dx, y + dy))
+                        .count() as u8;
+                    let survives = match alive(x, y) {
+                        true => rule.survive(count),
+                        false => rule.born(count),
+                    };
+                    if survives {
+                        next.insert((x, y));
                     }
                 }
+            }
+            live = next;
+            background = match background {
+                true => rule.survive(8),
+                false => rule.born(0),
+            };
+        }
+        (live, background)
+    }
+
+    #[test]
+    fn b0_rules_match_running_them_directly() {
+        let r_pentomino = [(1, 0), (2, 0), (0, 1), (1, 1), (1, 2)];
+        for rulestring in ["B0/S", "B03/S23", "B0123/S0238"] {
+            for suffix in ["", ":T20,20", ":P20,20"] {
+                let rule: Rule = format!("{}{}", rulestring, suffix).parse().unwrap();
+                let topology = rule.topology.unwrap_or(Topology::Plane);
+                // Wide enough on the plane that the pattern never reaches
+                // the background outside.
+                let window = topology.bounds().unwrap_or(((-12, -12), (14, 14)));
+
+                for mut board in [Board::new(), Board::with_engine(Sequential)] {
+                    board.topology = topology;
+                    for (x, y) in r_pentomino {
+                        board.set(x, y, true);
+                    }
 
-                for ch in born_part.chars() {
-                    if let Some(digit) = ch.to_digit(10) {
-                        if digit < 8 {
-                            rule_value |= 1 << (digit + 8);
-                        } else {
-                            return Err(de::Error::custom("Birth count out of range"));
+                    for generation in 1..=8 {
+                        board.advance(&rule);
+                        let (live, background) = run_directly(
+                            rule.life_like().unwrap(),
+                            topology,
+                            window,
+                            r_pentomino.into_iter().collect(),
+                            generation,
+                        );
+                        let inverted = rule.background_alive(generation, topology);
+                        let ((x0, y0), (x1, y1)) = window;
+                        for y in y0..=y1 {
+                            for x in x0..=x1 {
+                                assert_eq!(
+                                    board.get(x, y) != inverted,
+                                    live.contains(&(x, y)),
+                                    "{} at ({}, {}) on generation {}",
+                                    rule.serialize(),
+                                    x,
+                                    y,
+                                    generation
+                                );
+                            }
+                        }
+                        if topology == Topology::Plane {
+                            assert_eq!(inverted, background, "{}", rule.serialize());
                         }
-                    } else {
-                        return Err(de::Error::custom("Invalid character in birth string"));
                     }
                 }
-
-                Ok(Rule(rule_value))
             }
         }
+    }
 
-        deserializer.deserialize_str(RuleVisitor)
+    #[test]
+    fn b0_fills_empty_bounded_planes() {
+        let rule: Rule = "B0/S:P20,20".parse().unwrap();
+        let mut board = Board::new();
+        board.topology = rule.topology.unwrap();
+        board.advance(&rule);
+        assert_eq!(board.population(), 400);
+        board.advance(&rule);
+        assert_eq!(board.population(), 0);
     }
 }
@@ -0,0 +1,204 @@
+use super::{classify, Behaviour, Classification, Rule, SoupSettings};
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// How many rules each parent is picked out of.
+const TOURNAMENT: usize = 3;
+
+/// A rule and how well its soups did.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Scored {
+    pub fitness: f64,
+    pub classification: Classification,
+}
+
+/// An evolutionary search over life-like rules for ones whose soups send out
+/// spaceships, live long and settle down. It only holds plain data, so it
+/// doubles as the checkpoint long searches resume from.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Search {
+    pub seed: u64,
+    /// The soups every rule is scored on, kept so that resumed searches
+    /// score rules the same way as before.
+    pub settings: SoupSettings,
+    /// How many generations of rules have been bred so far.
+    pub generation: u64,
+    pub population: Vec<Rule>,
+    /// The best rules found so far, best first.
+    pub hall_of_fame: Vec<Scored>,
+    /// Every rule classified so far by rulestring, since rules come back,
+    /// or why it couldn't be.
+    classified: HashMap<String, Result<Classification, String>>,
+}
+
+impl Search {
+    /// A search starting from `population` random rules, scored on the soups
+    /// of `settings`.
+    pub fn new(seed: u64, settings: SoupSettings, population: usize) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+        Self {
+            seed,
+            settings,
+            generation: 0,
+            population: (0..population).map(|_| Rule::random(&mut rng)).collect(),
+            hall_of_fame: Vec::new(),
+            classified: HashMap::new(),
+        }
+    }
+    /// How many different rules have been tried.
+    pub fn tried(&self) -> usize {
+        self.classified.len()
+    }
+    /// Scores the population, keeps the best in a hall of fame `hall_of_fame`
+    /// long and breeds the next generation. Every generation has its own
+    /// random numbers, so a resumed search carries on exactly as it would
+    /// have.
+    pub fn step(&mut self, hall_of_fame: usize) {
+        let settings = &self.settings;
+        let new: Vec<(String, Result<Classification, String>)> = self
+            .population
+            .par_iter()
+            .filter(|rule| !self.classified.contains_key(&Rule::serialize(rule)))
+            .map(|rule| (rule.serialize(), classify(rule, settings)))
+            .collect();
+        self.classified.extend(new);
+
+        let mut scored: Vec<(Rule, f64)> = self
+            .population
+            .iter()
+            .map(|rule| {
+                let fitness = match self.classified.get(&rule.serialize()) {
+                    Some(Ok(classification)) => fitness(classification, settings),
+                    _ => 0.,
+                };
+                (rule.clone(), fitness)
+            })
+            .collect();
+        scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+        for (rule, fitness) in &scored {
+            let rulestring = rule.serialize();
+            if self
+                .hall_of_fame
+                .iter()
+                .any(|entry| entry.classification.rule == rulestring)
+            {
+                continue;
+            }
+            if let Some(Ok(classification)) = self.classified.get(&rulestring) {
+                self.hall_of_fame.push(Scored {
+                    fitness: *fitness,
+                    classification: classification.clone(),
+                });
+            }
+        }
+        self.hall_of_fame
+            .sort_by(|a, b| b.fitness.total_cmp(&a.fitness));
+        self.hall_of_fame.truncate(hall_of_fame);
+
+        let mut rng = StdRng::seed_from_u64(
+            self.seed ^ (self.generation + 1).wrapping_mul(0x9e3779b97f4a7c15),
+        );
+        self.population = breed(&scored, &mut rng);
+        self.generation += 1;
+    }
+}
+
+/// How promising a rule's soups are, spaceships counting the most, then
+/// lasting long and then settling down rather than dying or exploding.
+pub fn fitness(classification: &Classification, settings: &SoupSettings) -> f64 {
+    let gliders = classification.gliders as f64 / classification.soups.max(1) as f64;
+    let lifespan = classification.lifespan / settings.generations.max(1) as f64;
+    let behaviour = match classification.behaviour {
+        Behaviour::Stable => 1.,
+        Behaviour::Chaotic => 0.5,
+        Behaviour::DiesOut | Behaviour::Explosive => 0.,
+    };
+
+    3. * gliders + lifespan + behaviour
+}
+
+/// The next generation: the best quarter as they are, then children of
+/// tournament winners, crossed over and mutated.
+fn breed(scored: &[(Rule, f64)], rng: &mut StdRng) -> Vec<Rule> {
+    let size = scored.len();
+    let mut next: Vec<Rule> = scored
+        .iter()
+        .take(size / 4)
+        .map(|(rule, _)| rule.clone())
+        .collect();
+
+    let pick = |rng: &mut StdRng| {
+        (0..TOURNAMENT)
+            .filter_map(|_| scored.choose(rng))
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(rule, _)| rule.clone())
+    };
+    while next.len() < size {
+        let (Some(a), Some(b)) = (pick(rng), pick(rng)) else {
+            break;
+        };
+        let child = a.crossover(&b, rng).unwrap_or(a);
+        let mut child = child.mutate(rng).unwrap_or(child);
+        if rng.gen_bool(0.5) {
+            child = child.mutate(rng).unwrap_or(child);
+        }
+        next.push(child);
+    }
+
+    next
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SETTINGS: SoupSettings = SoupSettings {
+        soups: 8,
+        size: 32,
+        density: 0.35,
+        generations: 500,
+        seed: 1,
+    };
+
+    fn score(rule: &str) -> f64 {
+        fitness(
+            &classify(&rule.parse().unwrap(), &SETTINGS).unwrap(),
+            &SETTINGS,
+        )
+    }
+
+    #[test]
+    fn fitness_weighs_spaceships_then_lifespan_then_settling() {
+        let classification = Classification {
+            rule: String::from("B36/S23"),
+            behaviour: Behaviour::Stable,
+            lifespan: 250.,
+            density: 0.1,
+            soups: 8,
+            gliders: 4,
+        };
+        assert_eq!(fitness(&classification, &SETTINGS), 3.);
+
+        let chaotic = Classification {
+            behaviour: Behaviour::Chaotic,
+            ..classification.clone()
+        };
+        assert_eq!(fitness(&chaotic, &SETTINGS), 2.5);
+    }
+
+    #[test]
+    fn spaceship_rules_score_highest() {
+        // HighLife sends gliders out of most soups, B3/S013 settles without
+        // any and B/S dies at once.
+        let highlife = score("B36/S23");
+        let still = score("B3/S013");
+        let empty = score("B/S");
+
+        assert!(highlife > 3., "{}", highlife);
+        assert!(highlife > still && still > empty);
+        assert_eq!(empty, 1. / 500.);
+    }
+}
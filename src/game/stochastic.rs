@@ -0,0 +1,170 @@
+use super::{RuleError, RuleErrorKind};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+/// Chances are stored in billionths, so rules compare and print exactly.
+const CERTAIN: u32 = 1_000_000_000;
+
+/// A life-like rule where every neighbour count has a chance of birth and
+/// survival instead of a yes or no, written like `B36(0.1)/S23(0.99)`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Stochastic {
+    born: [u32; 9],
+    survive: [u32; 9],
+}
+
+impl Stochastic {
+    /// A rule with the chances of birth and survival on each count, as
+    /// [`parse_chances`] reads them.
+    pub fn new(born: [u32; 9], survive: [u32; 9]) -> Result<Self, String> {
+        if born[0] != 0 {
+            return Err(String::from(
+                "Rules where empty space comes alive aren't supported",
+            ));
+        }
+
+        Ok(Stochastic { born, survive })
+    }
+
+    /// Whether a cell is alive next generation, drawing from `rng` when its
+    /// fate isn't certain.
+    pub fn alive_next(&self, alive: bool, count: u32, rng: &mut impl Rng) -> bool {
+        let chance = match alive {
+            true => self.survive[count as usize],
+            false => self.born[count as usize],
+        };
+        match chance {
+            0 => false,
+            CERTAIN => true,
+            _ => rng.gen_range(0..CERTAIN) < chance,
+        }
+    }
+
+    pub fn serialize(&self) -> String {
+        format!(
+            "B{}/S{}",
+            serialize_chances(&self.born),
+            serialize_chances(&self.survive)
+        )
+    }
+}
+
+/// The random numbers for one chunk on one generation. Every chunk gets its
+/// own, so the result doesn't depend on the order chunks are computed in.
+pub fn chunk_rng(seed: u64, generation: u64, (cx, cy): (i64, i64)) -> StdRng {
+    let mut key = [0; 32];
+    for (bytes, value) in key
+        .chunks_exact_mut(8)
+        .zip([seed, generation, cx as u64, cy as u64])
+    {
+        bytes.copy_from_slice(&value.to_le_bytes());
+    }
+    StdRng::from_seed(key)
+}
+
+/// Parses birth or survival conditions without their `B` or `S`. Each count
+/// may be followed by its chance in parentheses, and is certain otherwise.
+/// Errors point into `text`.
+pub fn parse_chances(text: &str, max_count: u8) -> Result<[u32; 9], RuleError> {
+    let error = |message: String, position: usize| {
+        RuleError::new(RuleErrorKind::Invalid(message), Some(position), text)
+    };
+    let mut chances = [0; 9];
+    let mut rest = text;
+
+    while let Some(c) = rest.chars().next() {
+        let position = text.len() - rest.len();
+        rest = &rest[c.len_utf8()..];
+        let count = c
+            .to_digit(10)
+            .filter(|&count| count <= max_count as u32)
+            .ok_or_else(|| {
+                let message = format!("Invalid count '{}': counts go up to {}", c, max_count);
+                error(message, position)
+            })?;
+
+        chances[count as usize] = match rest.strip_prefix('(') {
+            Some(inner) => {
+                let start = text.len() - inner.len();
+                let (chance, after) = inner.split_once(')').ok_or_else(|| {
+                    RuleError::new(RuleErrorKind::Expected(')'), Some(text.len()), text)
+                })?;
+                rest = after;
+                parse_chance(chance).ok_or_else(|| {
+                    let message = format!("Invalid chance '{}': chances go from 0 to 1", chance);
+                    error(message, start)
+                })?
+            }
+            None => CERTAIN,
+        };
+    }
+
+    Ok(chances)
+}
+
+/// Reads a decimal from 0 to 1 in billionths.
+fn parse_chance(text: &str) -> Option<u32> {
+    let (whole, fraction) = text.split_once('.').unwrap_or((text, ""));
+    if whole.is_empty() && fraction.is_empty() {
+        return None;
+    }
+    if fraction.len() > 9 || !fraction.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+
+    let whole = match whole {
+        "0" | "" => 0,
+        "1" => CERTAIN,
+        _ => return None,
+    };
+    let fraction = format!("{:0<9}", fraction).parse::<u32>().ok()?;
+    Some(whole + fraction).filter(|&chance| chance <= CERTAIN)
+}
+
+fn serialize_chances(chances: &[u32; 9]) -> String {
+    let mut text = String::new();
+    for (count, &chance) in chances.iter().enumerate() {
+        match chance {
+            0 => {}
+            CERTAIN => text.push_str(&count.to_string()),
+            _ => {
+                let fraction = format!("{:09}", chance);
+                text.push_str(&format!("{}(0.{})", count, fraction.trim_end_matches('0')));
+            }
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game::{Board, Rule, Topology};
+
+    #[test]
+    fn seeded_runs_replay_on_any_number_of_threads() {
+        let rule: Rule = "B36(0.5)/S23(0.9)".parse().unwrap();
+        let pool = |threads: usize| {
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()
+                .unwrap()
+        };
+        let (single, multi) = (pool(1), pool(4));
+
+        let mut one = Board::soup(Topology::Plane, 2);
+        one.seed = 42;
+        let mut many = one.clone();
+        let start = one.sorted_cells();
+
+        for generation in 1..=20 {
+            single.install(|| one.advance(&rule));
+            multi.install(|| many.advance(&rule));
+            assert_eq!(
+                one.sorted_cells(),
+                many.sorted_cells(),
+                "generation {}",
+                generation
+            );
+        }
+        assert_ne!(one.sorted_cells(), start);
+    }
+}
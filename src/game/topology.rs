@@ -0,0 +1,353 @@
+use super::{RuleError, RuleErrorKind};
+use serde::de::{self, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::{fmt, str::FromStr};
+
+/// How the edges of the board are joined, using Golly's suffix notation
+/// (`T120,80`, `K120*,80`, ...). Bounded topologies are centred on the origin.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Topology {
+    /// The unbounded plane.
+    Plane,
+    /// A finite plane where everything past the edge is dead (`P`).
+    Bounded { width: i64, height: i64 },
+    /// Left/right and top/bottom edges joined (`T`).
+    Torus { width: i64, height: i64 },
+    /// A torus where one pair of edges is joined with a twist (`K`).
+    Klein {
+        width: i64,
+        height: i64,
+        twist: Twist,
+    },
+    /// Both pairs of edges joined with a twist (`C`).
+    CrossSurface { width: i64, height: i64 },
+}
+
+/// Which pair of edges of a Klein bottle is twisted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Twist {
+    /// Top and bottom edges, written as `K120*,80`.
+    Horizontal,
+    /// Left and right edges, written as `K120,80*`.
+    Vertical,
+}
+
+impl Topology {
+    pub fn size(&self) -> Option<(i64, i64)> {
+        match *self {
+            Topology::Plane => None,
+            Topology::Bounded { width, height }
+            | Topology::Torus { width, height }
+            | Topology::Klein { width, height, .. }
+            | Topology::CrossSurface { width, height } => Some((width, height)),
+        }
+    }
+    /// The inclusive corners of the region cells can live in.
+    pub fn bounds(&self) -> Option<((i64, i64), (i64, i64))> {
+        self.size().map(|(w, h)| {
+            let (x0, y0) = (-w / 2, -h / 2);
+            ((x0, y0), (x0 + w - 1, y0 + h - 1))
+        })
+    }
+    pub fn contains(&self, x: i64, y: i64) -> bool {
+        match self.bounds() {
+            None => true,
+            Some(((x0, y0), (x1, y1))) => x >= x0 && x <= x1 && y >= y0 && y <= y1,
+        }
+    }
+    /// Maps a coordinate that may lie past an edge onto the cell it refers
+    /// to, or `None` if it is always dead.
+    pub fn resolve(&self, x: i64, y: i64) -> Option<(i64, i64)> {
+        let Some(((x0, y0), _)) = self.bounds() else {
+            return Some((x, y));
+        };
+        let (w, h) = self.size().unwrap();
+        let (mut lx, mut ly) = (x - x0, y - y0);
+
+        let (twist_x, twist_y) = match *self {
+            Topology::Plane => unreachable!(),
+            Topology::Bounded { .. } => {
+                return self.contains(x, y).then_some((x, y));
+            }
+            Topology::Torus { .. } => (false, false),
+            Topology::Klein { twist, .. } => (twist == Twist::Vertical, twist == Twist::Horizontal),
+            Topology::CrossSurface { .. } => (true, true),
+        };
+
+        // Crossing a twisted edge mirrors the other axis.
+        let (wraps_x, wraps_y) = (lx.div_euclid(w), ly.div_euclid(h));
+        lx = lx.rem_euclid(w);
+        ly = ly.rem_euclid(h);
+        if twist_x && wraps_x % 2 != 0 {
+            ly = h - 1 - ly;
+        }
+        if twist_y && wraps_y % 2 != 0 {
+            lx = w - 1 - lx;
+        }
+
+        Some((x0 + lx, y0 + ly))
+    }
+    pub fn serialize(&self) -> String {
+        match *self {
+            Topology::Plane => String::from("plane"),
+            Topology::Bounded { width, height } => format!("P{},{}", width, height),
+            Topology::Torus { width, height } => format!("T{},{}", width, height),
+            Topology::Klein {
+                width,
+                height,
+                twist: Twist::Horizontal,
+            } => format!("K{}*,{}", width, height),
+            Topology::Klein {
+                width,
+                height,
+                twist: Twist::Vertical,
+            } => format!("K{},{}*", width, height),
+            Topology::CrossSurface { width, height } => format!("C{},{}", width, height),
+        }
+    }
+}
+
+impl FromStr for Topology {
+    type Err = RuleError;
+
+    /// Parses Golly's suffixes, except that sizes of 0, which Golly uses for
+    /// edges that go on forever, aren't supported: every bounded topology is
+    /// bounded both ways.
+    fn from_str(suffix: &str) -> Result<Self, RuleError> {
+        let error =
+            |kind: RuleErrorKind, position: usize| RuleError::new(kind, Some(position), suffix);
+
+        let trimmed = suffix.trim_start().trim_start_matches(':');
+        let start = suffix.len() - trimmed.len();
+        let text = trimmed.trim_end();
+
+        if text.is_empty() || text.eq_ignore_ascii_case("plane") {
+            return Ok(Topology::Plane);
+        }
+
+        let first = text.chars().next().unwrap();
+        let kind = first.to_ascii_uppercase();
+        if !matches!(kind, 'P' | 'T' | 'K' | 'C') {
+            return Err(error(RuleErrorKind::Unexpected(first), start));
+        }
+        let dims = &text[1..];
+        let (width, height) = match dims.split_once(',') {
+            Some((width, height)) => ((width, start + 1), (height, start + 2 + width.len())),
+            None => ((dims, start + 1), (dims, start + 1)),
+        };
+
+        // A size and the offset of its twist, if it has one.
+        let parse = |(text, offset): (&str, usize)| -> Result<(i64, Option<usize>), RuleError> {
+            let (digits, twist) = match text.strip_suffix('*') {
+                Some(digits) => (digits, Some(offset + digits.len())),
+                None => (text, None),
+            };
+            if let Some((position, ch)) = digits.char_indices().find(|(_, ch)| !ch.is_ascii_digit())
+            {
+                return Err(error(RuleErrorKind::Unexpected(ch), offset + position));
+            }
+            match digits.parse::<i64>() {
+                Ok(0) => Err(error(
+                    RuleErrorKind::Invalid(String::from(
+                        "Topology sizes must be positive; edges that go on forever aren't supported",
+                    )),
+                    offset,
+                )),
+                Ok(n) => Ok((n, twist)),
+                Err(_) => Err(error(
+                    RuleErrorKind::Invalid(String::from("Expected a topology size")),
+                    offset,
+                )),
+            }
+        };
+        let (width, twist_w) = parse(width)?;
+        let (height, twist_h) = parse(height)?;
+        // `K100*` is square, with the one size standing for both.
+        let twist_h = twist_h.filter(|_| dims.contains(','));
+
+        if kind == 'K' {
+            let twist = match (twist_w, twist_h) {
+                (Some(_), None) => Twist::Horizontal,
+                (None, Some(_)) => Twist::Vertical,
+                (None, None) => {
+                    return Err(error(RuleErrorKind::Expected('*'), start + text.len()))
+                }
+                // Only one pair of edges of a Klein bottle can be twisted.
+                (Some(_), Some(position)) => {
+                    return Err(error(RuleErrorKind::Unexpected('*'), position))
+                }
+            };
+            return Ok(Topology::Klein {
+                width,
+                height,
+                twist,
+            });
+        }
+        if let Some(position) = twist_w.or(twist_h) {
+            return Err(error(RuleErrorKind::Unexpected('*'), position));
+        }
+
+        Ok(match kind {
+            'P' => Topology::Bounded { width, height },
+            'T' => Topology::Torus { width, height },
+            _ => Topology::CrossSurface { width, height },
+        })
+    }
+}
+
+impl Serialize for Topology {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.serialize())
+    }
+}
+
+impl<'de> Deserialize<'de> for Topology {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct TopologyVisitor;
+
+        impl<'de> Visitor<'de> for TopologyVisitor {
+            type Value = Topology;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a topology, like 'plane' or 'T120,80'")
+            }
+
+            fn visit_str<E>(self, value: &str) -> Result<Topology, E>
+            where
+                E: de::Error,
+            {
+                value.parse().map_err(de::Error::custom)
+            }
+        }
+
+        deserializer.deserialize_str(TopologyVisitor)
+    }
+}
+
+#[cfg(test)]
+impl Topology {
+    /// Every topology with edges, at the given size.
+    pub fn every_bounded(width: i64, height: i64) -> [Topology; 5] {
+        [
+            Topology::Bounded { width, height },
+            Topology::Torus { width, height },
+            Topology::Klein {
+                width,
+                height,
+                twist: Twist::Horizontal,
+            },
+            Topology::Klein {
+                width,
+                height,
+                twist: Twist::Vertical,
+            },
+            Topology::CrossSurface { width, height },
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{Board, Engine, Parallel, Rule, Sequential};
+
+    #[test]
+    fn parses_golly_suffixes() {
+        let parse = |text: &str| text.parse::<Topology>();
+
+        assert_eq!(parse("plane"), Ok(Topology::Plane));
+        assert_eq!(
+            parse("T120,80"),
+            Ok(Topology::Torus {
+                width: 120,
+                height: 80
+            })
+        );
+        assert_eq!(
+            parse("P50"),
+            Ok(Topology::Bounded {
+                width: 50,
+                height: 50
+            })
+        );
+        assert_eq!(
+            parse("K120*,80"),
+            Ok(Topology::Klein {
+                width: 120,
+                height: 80,
+                twist: Twist::Horizontal
+            })
+        );
+        assert_eq!(
+            parse("K120,80*"),
+            Ok(Topology::Klein {
+                width: 120,
+                height: 80,
+                twist: Twist::Vertical
+            })
+        );
+        for topology in ["T120,80", "P50,50", "K120*,80", "K120,80*", "C64,32"] {
+            assert_eq!(parse(topology).unwrap().serialize(), topology);
+        }
+    }
+
+    #[test]
+    fn rejects_twists_where_they_dont_belong() {
+        let position = |text: &str| text.parse::<Topology>().unwrap_err().position;
+
+        // Both edges of a Klein bottle, or edges of anything else.
+        assert_eq!(position("K120*,80*"), Some(8));
+        assert_eq!(position("T120*,80"), Some(4));
+        assert_eq!(position("P120,80*"), Some(7));
+        assert_eq!(position("C120*,80"), Some(4));
+        assert_eq!(position("K120,80"), Some(7));
+    }
+
+    #[test]
+    fn rejects_zero_and_bad_sizes() {
+        let error = |text: &str| text.parse::<Topology>().unwrap_err();
+
+        assert_eq!(error("T0,100").position, Some(1));
+        assert_eq!(error("T100,0").position, Some(5));
+        assert_eq!(error("T-5,5").kind, RuleErrorKind::Unexpected('-'));
+        assert_eq!(error("Q10,10").kind, RuleErrorKind::Unexpected('Q'));
+    }
+
+    #[test]
+    fn rule_suffix_errors_point_into_the_rulestring() {
+        let err = "B3/S23:K10*,10*".parse::<Rule>().unwrap_err();
+        assert_eq!(err.position, Some(14));
+        assert_eq!(err.rulestring, "B3/S23:K10*,10*");
+    }
+
+    #[test]
+    fn engines_agree_on_every_topology() {
+        let rule: Rule = "B3/S23".parse().unwrap();
+
+        // Small enough that soups reach the edges from both sides of a chunk
+        // boundary.
+        let topologies = Topology::every_bounded(90, 70);
+        for topology in std::iter::once(Topology::Plane).chain(topologies) {
+            let mut sequential = Board::soup(topology, 1);
+            let mut parallel = sequential.clone();
+
+            for generation in 0..40 {
+                Sequential.advance(&mut sequential, &rule);
+                Parallel.advance(&mut parallel, &rule);
+                assert_eq!(
+                    sequential.sorted_cells(),
+                    parallel.sorted_cells(),
+                    "{} after {} generations",
+                    topology.serialize(),
+                    generation + 1
+                );
+            }
+        }
+    }
+}
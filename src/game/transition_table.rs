@@ -0,0 +1,80 @@
+use super::{RuleError, RuleErrorKind};
+use base64::engine::general_purpose::STANDARD_NO_PAD;
+use base64::{DecodeError, Engine as _};
+
+/// Whether a cell is alive next generation for each of the 512 states of its
+/// 3x3 neighbourhood, the form behind Golly and LifeViewer's `MAP` rules.
+///
+/// Entries are indexed by the neighbourhood read from NW to SE, most
+/// significant bit first, with the cell itself between W and E.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TransitionTable([u64; 8]);
+
+impl TransitionTable {
+    pub fn from_fn(mut f: impl FnMut(bool, u8) -> bool) -> Self {
+        let mut table = TransitionTable([0; 8]);
+        for neighbours in 0..=255u8 {
+            for alive in [false, true] {
+                if f(alive, neighbours) {
+                    let i = Self::index(alive, neighbours);
+                    table.0[i / 64] |= 1 << (i % 64);
+                }
+            }
+        }
+        table
+    }
+
+    /// `neighbours` lists the 8 neighbours from NW to SE, most significant
+    /// bit first.
+    pub fn get(&self, alive: bool, neighbours: u8) -> bool {
+        let i = Self::index(alive, neighbours);
+        (self.0[i / 64] >> (i % 64)) & 1 == 1
+    }
+
+    fn index(alive: bool, neighbours: u8) -> usize {
+        let n = neighbours as usize;
+        ((n & 0xf0) << 1) | ((alive as usize) << 4) | (n & 0x0f)
+    }
+
+    /// Parses the base64 part of a `MAP` rulestring. Errors point into
+    /// `text`.
+    pub fn from_map(text: &str) -> Result<Self, RuleError> {
+        let trimmed = text.trim_end_matches('=');
+        let bytes = STANDARD_NO_PAD.decode(trimmed).map_err(|err| {
+            let position = match err {
+                DecodeError::InvalidByte(position, _)
+                | DecodeError::InvalidLastSymbol(position, _) => Some(position),
+                _ => Some(trimmed.len()),
+            };
+            let message = format!("Invalid MAP rule: {}", err);
+            RuleError::new(RuleErrorKind::Invalid(message), position, text)
+        })?;
+        if bytes.len() != 64 {
+            let message = format!("MAP rules need 512 bits, got {}", bytes.len() * 8);
+            return Err(RuleError::new(
+                RuleErrorKind::Invalid(message),
+                Some(trimmed.len()),
+                text,
+            ));
+        }
+
+        let mut table = TransitionTable([0; 8]);
+        for i in 0..512 {
+            if (bytes[i / 8] >> (7 - i % 8)) & 1 == 1 {
+                table.0[i / 64] |= 1 << (i % 64);
+            }
+        }
+        Ok(table)
+    }
+
+    /// The base64 part of a `MAP` rulestring, without padding.
+    pub fn to_map(self) -> String {
+        let mut bytes = [0u8; 64];
+        for i in 0..512 {
+            if (self.0[i / 64] >> (i % 64)) & 1 == 1 {
+                bytes[i / 8] |= 1 << (7 - i % 8);
+            }
+        }
+        STANDARD_NO_PAD.encode(bytes)
+    }
+}
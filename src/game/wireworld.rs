@@ -0,0 +1,98 @@
+use super::RuleTree;
+use lazy_static::lazy_static;
+
+/// The name of Brian Silverman's WireWorld, where electrons run along wires
+/// drawn on an empty background.
+pub const WIREWORLD: &str = "WireWorld";
+
+pub const ELECTRON_HEAD: u8 = 1;
+pub const ELECTRON_TAIL: u8 = 2;
+pub const CONDUCTOR: u8 = 3;
+
+lazy_static! {
+    pub static ref WIREWORLD_TREE: RuleTree = RuleTree::from_fn(4, |state, neighbours| {
+        let heads = neighbours
+            .iter()
+            .filter(|&&neighbour| neighbour == ELECTRON_HEAD)
+            .count();
+        wireworld_next_state(state, heads)
+    });
+}
+
+/// Heads turn into tails, tails back into conductors, and conductors carry a
+/// head when one or two of their neighbours are heads.
+pub fn wireworld_next_state(state: u8, heads: usize) -> u8 {
+    match state {
+        ELECTRON_HEAD => ELECTRON_TAIL,
+        ELECTRON_TAIL => CONDUCTOR,
+        CONDUCTOR if heads == 1 || heads == 2 => ELECTRON_HEAD,
+        state => state,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{Board, Rule};
+
+    #[test]
+    fn conductors_need_one_or_two_heads() {
+        for heads in 0..=8 {
+            let expected = match heads {
+                1 | 2 => ELECTRON_HEAD,
+                _ => CONDUCTOR,
+            };
+            assert_eq!(wireworld_next_state(CONDUCTOR, heads), expected);
+            assert_eq!(wireworld_next_state(ELECTRON_HEAD, heads), ELECTRON_TAIL);
+            assert_eq!(wireworld_next_state(ELECTRON_TAIL, heads), CONDUCTOR);
+            assert_eq!(wireworld_next_state(0, heads), 0);
+        }
+    }
+
+    #[test]
+    fn electrons_go_round_a_clock_loop() {
+        // A loop with its corners cut, since a wire round a square corner
+        // touches the cell before the turn and sends electrons back. Listed
+        // in order round it.
+        let path = [
+            (1, 0),
+            (2, 0),
+            (3, 0),
+            (4, 1),
+            (4, 2),
+            (3, 3),
+            (2, 3),
+            (1, 3),
+            (0, 2),
+            (0, 1),
+        ];
+
+        let rule: Rule = WIREWORLD.parse().unwrap();
+        let mut board = Board::new();
+        for &(x, y) in &path {
+            board.set_state(x, y, CONDUCTOR);
+        }
+        board.set_state(1, 0, ELECTRON_TAIL);
+        board.set_state(2, 0, ELECTRON_HEAD);
+        let start = board.sorted_cells();
+
+        for generation in 1..=path.len() {
+            board.advance(&rule);
+
+            // The electron moves one cell along, leaving its tail behind and
+            // the wire before that as it was.
+            let head = path[(1 + generation) % path.len()];
+            let tail = path[generation % path.len()];
+            for &(x, y) in &path {
+                let expected = match (x, y) {
+                    cell if cell == head => ELECTRON_HEAD,
+                    cell if cell == tail => ELECTRON_TAIL,
+                    _ => CONDUCTOR,
+                };
+                assert_eq!(board.state(x, y), expected, "generation {}", generation);
+            }
+            assert_eq!(board.cells().count(), path.len());
+        }
+        assert_eq!(board.sorted_cells(), start);
+    }
+}
@@ -0,0 +1,193 @@
+/// A one dimensional rule in Wolfram's numbering, where each cell only sees
+/// the cells within `range` of it on its own row.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Wolfram {
+    pub range: u8,
+    /// Whether the rule only depends on how many of those cells are alive,
+    /// rather than which.
+    pub totalistic: bool,
+    /// Bit `n` says whether a cell is alive next generation when the pattern
+    /// of its neighbourhood (west first, from the most significant bit), or
+    /// for totalistic rules its number of live cells, is `n`.
+    code: u64,
+}
+
+/// Totalistic codes have a bit for each count up to `2 * range + 1`, which
+/// has to fit in a `u64`.
+const MAX_RANGE: u8 = 31;
+
+impl Wolfram {
+    /// Whether `text` looks like `W30` or `T20R2`, so that it isn't taken
+    /// for the name of a rule file.
+    pub fn matches(text: &str) -> bool {
+        text.starts_with(['W', 'w', 'T', 't'])
+            && text[1..].starts_with(|c: char| c.is_ascii_digit())
+    }
+
+    /// Parses an elementary rule like `W30` or a totalistic one like `T20R2`
+    /// (the range defaults to 1).
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let (letter, rest) = text.split_at(1);
+        let totalistic = letter.eq_ignore_ascii_case("T");
+
+        let (code, range) = match rest.split_once(['R', 'r']) {
+            Some((code, range)) if totalistic => (code, range),
+            Some(_) => return Err(String::from("Elementary rules have a range of 1")),
+            None => (rest, "1"),
+        };
+        let range = range
+            .parse::<u8>()
+            .ok()
+            .filter(|range| (1..=MAX_RANGE).contains(range))
+            .ok_or_else(|| format!("Range must be 1-{}", MAX_RANGE))?;
+
+        let bits = if totalistic { 2 * range + 2 } else { 8 };
+        let code = code
+            .parse::<u64>()
+            .ok()
+            .filter(|&code| bits == 64 || code >> bits == 0)
+            .ok_or_else(|| match totalistic {
+                true => format!(
+                    "Codes of range {} go up to {}",
+                    range,
+                    u64::MAX >> (64 - bits)
+                ),
+                false => String::from("Elementary rules are numbered 0-255"),
+            })?;
+
+        Ok(Wolfram {
+            range,
+            totalistic,
+            code,
+        })
+    }
+
+    /// Whether an empty row comes alive, which would switch on the whole
+    /// infinite row.
+    pub fn has_b0(&self) -> bool {
+        self.code & 1 == 1
+    }
+
+    /// Whether a row that's all alive stays that way.
+    pub fn keeps_full(&self) -> bool {
+        self.alive_next(std::iter::repeat_n(true, self.size()))
+    }
+
+    /// The rule taking a row stored inverted if `from` is set to the next one,
+    /// stored inverted if `to` is set. See [`Rule::background_alive`].
+    ///
+    /// [`Rule::background_alive`]: super::Rule::background_alive
+    pub fn emulated(&self, from: bool, to: bool) -> Self {
+        let size = self.size();
+        let (indices, all) = match self.totalistic {
+            true => (size + 1, size),
+            false => (1 << size, (1 << size) - 1),
+        };
+
+        let mut code = 0;
+        for index in 0..indices {
+            let real = match (from, self.totalistic) {
+                (false, _) => index,
+                (true, true) => all - index,
+                (true, false) => all ^ index,
+            };
+            code |= ((((self.code >> real) & 1 == 1) != to) as u64) << index;
+        }
+
+        Wolfram { code, ..*self }
+    }
+
+    /// How many cells each cell sees, itself included.
+    fn size(&self) -> usize {
+        2 * self.range as usize + 1
+    }
+
+    /// Whether a cell is alive next generation, given whether each cell in
+    /// its neighbourhood is, from west to east.
+    pub fn alive_next(&self, cells: impl Iterator<Item = bool>) -> bool {
+        let index = if self.totalistic {
+            cells.filter(|&alive| alive).count()
+        } else {
+            cells.fold(0, |pattern, alive| (pattern << 1) | alive as usize)
+        };
+        (self.code >> index) & 1 == 1
+    }
+
+    pub fn serialize(&self) -> String {
+        match (self.totalistic, self.range) {
+            (false, _) => format!("W{}", self.code),
+            (true, 1) => format!("T{}", self.code),
+            (true, range) => format!("T{}R{}", self.code, range),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{current_row, Board, Rule, Topology};
+
+    #[test]
+    fn parses_every_elementary_rule() {
+        for code in 0..=255 {
+            let wolfram = Wolfram::parse(&format!("W{}", code)).unwrap();
+            assert_eq!(wolfram.serialize(), format!("W{}", code));
+        }
+        assert!(Wolfram::parse("W256").is_err());
+    }
+
+    /// Runs `wolfram` from a single live cell on a row wide enough that the
+    /// infinite background beyond it can be a single value.
+    fn naive(wolfram: &Wolfram, generations: usize) -> Vec<Vec<bool>> {
+        let range = wolfram.range as usize;
+        let width = 2 * range * generations + 1;
+        let mut row = vec![false; width];
+        row[width / 2] = true;
+        let mut background = false;
+
+        let mut rows = vec![row.clone()];
+        for _ in 0..generations {
+            let cell = |x: i64| {
+                usize::try_from(x)
+                    .ok()
+                    .and_then(|x| row.get(x).copied())
+                    .unwrap_or(background)
+            };
+            let next = (0..width as i64)
+                .map(|x| wolfram.alive_next((x - range as i64..=x + range as i64).map(cell)))
+                .collect();
+            background = wolfram.alive_next(std::iter::repeat_n(background, 2 * range + 1));
+            row = next;
+            rows.push(row.clone());
+        }
+        rows
+    }
+
+    #[test]
+    fn odd_rules_match_running_them_directly() {
+        let generations = 12;
+
+        for rulestring in ["W30", "W1", "W73", "W129", "W255", "T3", "T21R2"] {
+            let rule: Rule = rulestring.parse().unwrap();
+            let wolfram = *rule.wolfram().unwrap();
+            let expected = naive(&wolfram, generations);
+
+            let mut board = Board::new();
+            board.set(0, 0, true);
+            for _ in 0..generations {
+                board.advance(&rule);
+            }
+            assert_eq!(current_row(&board), -(generations as i64));
+
+            let half = (expected[0].len() / 2) as i64;
+            for (generation, row) in expected.iter().enumerate() {
+                // Rows where the background is alive are stored inverted.
+                let inverted = rule.background_alive(generation as u64, Topology::Plane);
+                let stored = (-half..=half)
+                    .map(|x| board.get(x, -(generation as i64)) != inverted)
+                    .collect::<Vec<_>>();
+                assert_eq!(&stored, row, "{} on generation {}", rulestring, generation);
+            }
+        }
+    }
+}
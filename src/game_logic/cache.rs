@@ -2,39 +2,54 @@ use crate::config::CONFIG;
 
 #[derive(Clone)]
 pub struct Cache {
-    pub half_board_width: f32,
-    pub half_board_height: f32,
     pub tile_size: f32,
-    pub board_width: usize,
-    pub board_height: usize,
     pub scale_factor: f32,
     pub camera_offset: (f32, f32),
     pub window_size: (f32, f32),
     pub target_tile_size: f32,
     pub target_camera_offset: (f32, f32),
+    /// Whether cells are drawn as hexagons, with every row shifted half a
+    /// cell east of the one below.
+    pub hexagonal: bool,
 }
 
 impl Cache {
-    pub fn new(board_size: (usize, usize), tile_size: f32) -> Self {
+    pub fn new(tile_size: f32, hexagonal: bool) -> Self {
         Self {
-            half_board_width: board_size.0 as f32 / 2.,
-            half_board_height: board_size.1 as f32 / 2.,
             tile_size,
             target_tile_size: tile_size,
-            board_width: board_size.0,
-            board_height: board_size.1,
             scale_factor: 1.,
             camera_offset: (0., 0.),
             window_size: (0., 0.),
             target_camera_offset: (0., 0.),
+            hexagonal,
         }
     }
-    pub fn update(&mut self, board_size: (usize, usize), tile_size: f32) {
-        self.half_board_width = board_size.0 as f32 / 2.;
-        self.half_board_height = board_size.1 as f32 / 2.;
+    pub fn update(&mut self, tile_size: f32) {
         self.tile_size = tile_size * 0.9;
-        self.board_width = board_size.0;
-        self.board_height = board_size.1;
         self.scale_factor = tile_size / CONFIG.tile_size;
     }
+    /// The range of board cells (inclusive) currently visible in the window.
+    pub fn visible_cells(&self) -> ((i64, i64), (i64, i64)) {
+        let f = |window_side: f32, offset: f32| {
+            let centre = -offset * self.scale_factor / self.tile_size;
+            let half = window_side / 2. / self.tile_size + 1.;
+            (
+                (centre - half).floor() as i64,
+                (centre + half).ceil() as i64,
+            )
+        };
+
+        let (x, y) = (
+            f(self.window_size.0, self.camera_offset.0),
+            f(self.window_size.1, self.camera_offset.1),
+        );
+        if !self.hexagonal {
+            return (x, y);
+        }
+
+        // Undo the shift of the highest and lowest rows.
+        let shift = |y: i64| y.div_euclid(2);
+        ((x.0 - shift(y.1) - 1, x.1 - shift(y.0) + 1), y)
+    }
 }
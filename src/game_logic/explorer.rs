@@ -0,0 +1,121 @@
+use crate::prelude::*;
+use rand::Rng;
+
+/// The rules tried so far in the rule explorer, which runs a fresh soup under
+/// each one.
+#[derive(Clone, Debug)]
+pub struct Explorer {
+    candidates: Vec<Rule>,
+    index: usize,
+}
+
+impl Explorer {
+    pub fn new(rule: Rule) -> Self {
+        Self {
+            candidates: vec![rule],
+            index: 0,
+        }
+    }
+    pub fn current(&self) -> &Rule {
+        &self.candidates[self.index]
+    }
+    /// Which candidate is current, counting from 1, and how many there are.
+    pub fn position(&self) -> (usize, usize) {
+        (self.index + 1, self.candidates.len())
+    }
+    /// Moves on to the next candidate, making up a random rule past the end.
+    pub fn next(&mut self, rng: &mut impl Rng) -> &Rule {
+        if self.index + 1 == self.candidates.len() {
+            self.candidates.push(Rule::random(rng));
+        }
+        self.index += 1;
+        self.current()
+    }
+    /// Replaces the candidates after the current one with it with a count
+    /// flipped, or a random rule if it isn't totalistic, and moves on to it.
+    pub fn mutate(&mut self, rng: &mut impl Rng) -> &Rule {
+        let rule = self
+            .current()
+            .mutate(rng)
+            .unwrap_or_else(|| Rule::random(rng));
+        self.candidates.truncate(self.index + 1);
+        self.candidates.push(rule);
+        self.index += 1;
+        self.current()
+    }
+    pub fn previous(&mut self) -> Option<&Rule> {
+        self.index = self.index.checked_sub(1)?;
+        Some(self.current())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    fn life() -> Rule {
+        "B3/S23".parse().unwrap()
+    }
+
+    #[test]
+    fn going_back_and_forth_keeps_the_candidates() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let mut explorer = Explorer::new(life());
+        assert_eq!(explorer.previous(), None);
+        assert_eq!(explorer.position(), (1, 1));
+
+        let second = explorer.next(&mut rng).clone();
+        let third = explorer.next(&mut rng).clone();
+        assert_eq!(explorer.position(), (3, 3));
+
+        assert_eq!(explorer.previous(), Some(&second));
+        assert_eq!(explorer.previous(), Some(&life()));
+        assert_eq!(explorer.previous(), None);
+        assert_eq!(explorer.position(), (1, 3));
+
+        // Going forward again revisits the same rules before making up more.
+        assert_eq!(explorer.next(&mut rng), &second);
+        assert_eq!(explorer.next(&mut rng), &third);
+        explorer.next(&mut rng);
+        assert_eq!(explorer.position(), (4, 4));
+    }
+
+    #[test]
+    fn mutating_replaces_the_candidates_ahead() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let mut explorer = Explorer::new(life());
+        explorer.next(&mut rng);
+        explorer.next(&mut rng);
+        explorer.previous();
+        explorer.previous();
+
+        let mutated = explorer.mutate(&mut rng).clone();
+        assert_eq!(explorer.position(), (2, 2));
+        assert_ne!(mutated, life());
+        // One count flipped.
+        let life = life();
+        let (from, to) = (life.life_like().unwrap(), mutated.life_like().unwrap());
+        let flipped = (0..=8)
+            .filter(|&n| from.born(n) != to.born(n) || from.survive(n) != to.survive(n))
+            .count();
+        assert_eq!(flipped, 1);
+
+        assert_eq!(explorer.previous(), Some(&life));
+        assert_eq!(explorer.next(&mut rng), &mutated);
+    }
+
+    #[test]
+    fn seeds_replay_the_same_rules() {
+        let explore = |seed| {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let mut explorer = Explorer::new(life());
+            let mut rules = vec![explorer.next(&mut rng).clone()];
+            rules.push(explorer.mutate(&mut rng).clone());
+            rules.push(explorer.next(&mut rng).clone());
+            rules
+        };
+        assert_eq!(explore(8), explore(8));
+        assert_ne!(explore(8), explore(9));
+    }
+}
@@ -1,10 +1,27 @@
+use crate::cli::{append_line, classifications_path};
 use crate::prelude::*;
+use std::sync::{Arc, OnceLock};
+use std::thread;
 
 pub fn key_pressed(app: &App, model: &mut Model, key: Key) {
     fn clear(model: &mut Model) {
         model.selection = None;
     }
 
+    // Keys type into the rule editor while it's open, characters arriving
+    // through `received_character`.
+    if let Some(editor) = &mut model.rule_editor {
+        match key {
+            Key::Return | Key::NumpadEnter => switch_rule(app, model),
+            Key::Escape => close_rule_editor(app, model),
+            Key::Back => editor.backspace(),
+            Key::Up => editor.recall(&model.rule_history, true),
+            Key::Down => editor.recall(&model.rule_history, false),
+            _ => (),
+        }
+        return;
+    }
+
     if app.keys.mods.ctrl() {
         match key {
             Key::C => {
@@ -21,15 +38,13 @@ pub fn key_pressed(app: &App, model: &mut Model, key: Key) {
                 }
             }
             Key::S => {
-                save_board(model.board.clone());
-                notify_info("Board saved to file.");
-                clear(model)
-            }
-            Key::V => {
-                if model.clipboard.is_some() {
-                    Selection::paste(model);
+                match save_board(&model.board, &model.rule) {
+                    Ok(()) => notify_info("Board saved to file."),
+                    Err(err) => notify_error(err),
                 }
+                clear(model)
             }
+            Key::V if model.clipboard.is_some() => Selection::paste(model),
             _ => (),
         }
     } else {
@@ -73,8 +88,15 @@ pub fn key_pressed(app: &App, model: &mut Model, key: Key) {
                 model.paused = !model.paused;
                 clear(model)
             }
+            Key::C if app.keys.mods.shift() => {
+                model.board.clear_mask();
+                clear(model);
+            }
             Key::C => {
-                model.board.clear();
+                match &mut model.field {
+                    Some(field) => field.clear(),
+                    None => model.board.clear(),
+                }
                 clear(model);
             }
             Key::G => model.grid_lines = !model.grid_lines,
@@ -83,15 +105,270 @@ pub fn key_pressed(app: &App, model: &mut Model, key: Key) {
                 clear(model);
             }
             Key::F => model.show_info = !model.show_info,
+            Key::N if app.keys.mods.shift() => {
+                if !model.board.step_back(&model.rule) {
+                    notify_info("Only block rules can step back, and not past generation 0.");
+                }
+                clear(model);
+            }
             Key::N => {
-                model.board.advance();
+                match &mut model.field {
+                    Some(field) => field.step(),
+                    None => model.board.advance(&model.rule),
+                }
+                clear(model);
+            }
+            Key::R if model.field.is_some() => {
+                if let Some(field) = &mut model.field {
+                    field.randomize(&mut rand::thread_rng());
+                }
+            }
+            Key::R if model.rule.wolfram().is_some() => {
+                seed_row(model);
                 clear(model);
             }
+            Key::P if app.keys.mods.shift() => {
+                if let Some(selection) = model.selection.take() {
+                    let (min, max) = selection.bounds();
+                    model.board.clear_regions(min, max);
+                }
+            }
+            Key::P => {
+                if let Some(selection) = model.selection.take() {
+                    paint_region(model, &selection);
+                }
+            }
+            Key::O if !CONFIG.region_rules.is_empty() => {
+                model.region_rule = (model.region_rule + 1) % CONFIG.region_rules.len();
+                notify_info(format!(
+                    "Painting regions with {}.",
+                    CONFIG.region_rules[model.region_rule].serialize()
+                ));
+            }
+            Key::M => {
+                model.mask_tool = Mask::next_tool(model.mask_tool, CONFIG.clock_period);
+                notify_info(match model.mask_tool {
+                    None => String::from("Painting cells."),
+                    Some(Mask::On) => String::from("Painting walls that are always alive."),
+                    Some(Mask::Off) => String::from("Painting walls that are always dead."),
+                    Some(Mask::Clock { period, .. }) => {
+                        format!("Painting emitters with a period of {}.", period)
+                    }
+                });
+            }
             Key::K => {
                 model.show_keybinds = !model.show_keybinds;
                 clear(model);
             }
+            Key::T => match model.explorer.take() {
+                Some(_) => {
+                    model.record_rule();
+                    notify_info(format!("Stopped exploring, on {}.", model.rule.serialize()))
+                }
+                None => {
+                    model.explorer = Some(Explorer::new(model.rule.clone()));
+                    explore(model);
+                }
+            },
+            Key::Right if model.explorer.is_some() => {
+                if let Some(explorer) = &mut model.explorer {
+                    match app.keys.mods.shift() {
+                        true => explorer.mutate(&mut rand::thread_rng()),
+                        false => explorer.next(&mut rand::thread_rng()),
+                    };
+                }
+                explore(model);
+            }
+            Key::Left if model.explorer.is_some() => {
+                let moved = model.explorer.as_mut().and_then(Explorer::previous);
+                if moved.is_some() {
+                    explore(model);
+                }
+            }
+            Key::I if app.keys.mods.shift() => export_classification(model),
+            Key::I if model.classification.is_some() => model.classification = None,
+            Key::I if model.classifying.is_none() => start_classifying(model),
+            Key::L => {
+                let starred = star_rule(&model.rule);
+                // Rules tried while exploring only make the history once
+                // they're kept.
+                if starred.is_ok() && model.explorer.is_some() {
+                    model.record_rule();
+                }
+                match starred {
+                    Ok(true) => notify_info(format!("Starred {}.", model.rule.serialize())),
+                    Ok(false) => {
+                        notify_info(format!("{} is already starred.", model.rule.serialize()))
+                    }
+                    Err(err) => notify_error(err),
+                }
+            }
+            Key::Return | Key::NumpadEnter => {
+                model.rule_editor = Some(RuleEditor::new());
+                // Escape cancels instead of closing the app while typing.
+                app.set_exit_on_escape(false);
+            }
+            Key::Key1
+            | Key::Key2
+            | Key::Key3
+            | Key::Key4
+            | Key::Key5
+            | Key::Key6
+            | Key::Key7
+            | Key::Key8
+            | Key::Key9 => set_paint_state(model, key as u8 - Key::Key1 as u8 + 1),
+            Key::LBracket if model.paint_state > 1 => set_paint_state(model, model.paint_state - 1),
+            Key::RBracket => set_paint_state(model, model.paint_state.saturating_add(1)),
             _ => (),
         }
     }
 }
+
+/// Picks the state left clicks paint with, if the rule has it.
+fn set_paint_state(model: &mut Model, state: u8) {
+    if (state as u16) < model.rule.states() {
+        model.paint_state = state;
+        notify_info(format!("Painting state {}.", state));
+    }
+}
+
+/// Runs the selected cells under the rule picked with O.
+fn paint_region(model: &mut Model, selection: &Selection) {
+    let Some(rule) = CONFIG.region_rules.get(model.region_rule) else {
+        notify_error("There are no region rules in the config.");
+        return;
+    };
+
+    let (min, max) = selection.bounds();
+    let region = Region {
+        min,
+        max,
+        rule: rule.clone(),
+    };
+    match model.board.paint_region(region, &model.rule) {
+        Ok(()) => notify_info(format!("Region now runs {}.", rule.serialize())),
+        Err(err) => notify_error(err),
+    }
+}
+
+/// Switches the board to the rule typed into the editor, leaving the editor
+/// open if it can't.
+fn switch_rule(app: &App, model: &mut Model) {
+    let rule = match model
+        .rule_editor
+        .as_ref()
+        .and_then(|editor| editor.parsed.clone())
+    {
+        Some(Ok(rule)) => rule,
+        Some(Err(err)) => {
+            notify_error(err);
+            return;
+        }
+        None => {
+            close_rule_editor(app, model);
+            return;
+        }
+    };
+
+    match model.set_rule(rule) {
+        Ok(()) => {
+            notify_info(format!("Now running {}.", model.rule.serialize()));
+            close_rule_editor(app, model);
+        }
+        Err(err) => notify_error(err),
+    }
+}
+
+fn close_rule_editor(app: &App, model: &mut Model) {
+    model.rule_editor = None;
+    app.set_exit_on_escape(true);
+}
+
+/// Classifies the rule on another thread, which `update` picks up from.
+fn start_classifying(model: &mut Model) {
+    let result = Arc::new(OnceLock::new());
+    let (rule, done) = (model.rule.clone(), result.clone());
+    let settings = SoupSettings::from_config(CONFIG.seed.unwrap_or_else(rand::random));
+    thread::spawn(move || {
+        let _ = done.set(classify(&rule, &settings));
+    });
+
+    model.classifying = Some(result);
+    notify_info(format!("Classifying {}...", model.rule.serialize()));
+}
+
+/// Adds the classification on show to the ones saved in the data directory.
+fn export_classification(model: &Model) {
+    let Some(classification) = &model.classification else {
+        notify_info("Press I to classify the rule first.");
+        return;
+    };
+
+    let line = serde_json::to_string(classification).unwrap();
+    match append_line(&classifications_path(), &line) {
+        Ok(()) => notify_info(format!(
+            "Classification saved to {}.",
+            classifications_path()
+        )),
+        Err(err) => notify_error(err),
+    }
+}
+
+/// Runs the explorer's current rule on a fresh soup.
+fn explore(model: &mut Model) {
+    let Some(rule) = model
+        .explorer
+        .as_ref()
+        .map(|explorer| explorer.current().clone())
+    else {
+        return;
+    };
+
+    // Generation 0, which no rule stores inverted, so any rule can take over.
+    model.board.clear();
+    model.board.generation = 0;
+    if let Err(err) = model.switch_rule(rule) {
+        notify_error(err);
+        return;
+    }
+
+    let half = CONFIG.soup_size as i64 / 2;
+    model.board.randomize(
+        (-half, -half),
+        (half - 1, half - 1),
+        CONFIG.soup_density,
+        &mut rand::thread_rng(),
+    );
+    model.paused = false;
+    notify_info(format!("Exploring {}.", model.rule.serialize()));
+}
+
+/// Restarts a 1D rule from the top row of the selection, or from a random row
+/// across the window.
+fn seed_row(model: &mut Model) {
+    let row: Vec<(i64, bool)> = match &model.selection {
+        Some(selection) => {
+            let y = selection.start.y.max(selection.end.y);
+            let (x0, x1) = (
+                selection.start.x.min(selection.end.x),
+                selection.start.x.max(selection.end.x),
+            );
+            (x0..=x1).map(|x| (x, model.board.get(x, y))).collect()
+        }
+        None => {
+            let ((x0, x1), _) = model.cache.visible_cells();
+            (x0..=x1).map(|x| (x, rand::random())).collect()
+        }
+    };
+
+    model.board.clear();
+    model.board.generation = 0;
+    for (x, alive) in row {
+        model.board.set(x, 0, alive);
+    }
+
+    // Start with the seed near the top of the window.
+    let cache = &mut model.cache;
+    let centre = 5. - cache.window_size.1 / 2. / cache.tile_size;
+    cache.target_camera_offset.1 = -centre * cache.tile_size / cache.scale_factor;
+}
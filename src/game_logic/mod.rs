@@ -1,4 +1,5 @@
 pub use cache::*;
+pub use explorer::*;
 pub use keybinds::*;
 pub use model::*;
 pub use mouse::*;
@@ -9,6 +10,7 @@ pub use utils::*;
 pub use view::*;
 
 mod cache;
+mod explorer;
 mod keybinds;
 mod model;
 mod mouse;
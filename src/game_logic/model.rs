@@ -1,13 +1,21 @@
 use super::*;
-use crate::{prelude::*, ui::notify_info};
+use crate::cli::Args;
+use crate::{
+    prelude::*,
+    ui::{notify_error, notify_info},
+};
 use clap::Parser;
 use fps_ticker::Fps;
 use grid::Grid;
 use nannou::text::Font;
+use std::sync::{Arc, OnceLock};
 
 #[derive(Clone)]
 pub struct Model {
     pub board: Board,
+    /// The continuous automaton that runs instead of the board when the
+    /// config picks one.
+    pub field: Option<Field>,
     pub paused: bool,
     pub pressed: Option<MouseButton>,
     pub last_mouse_pos: (f32, f32),
@@ -16,30 +24,70 @@ pub struct Model {
     pub mouse_pos: (f32, f32),
     pub grid_lines: bool,
     pub symmetry: bool,
+    /// The state left clicks paint with.
+    pub paint_state: u8,
+    /// What clicks paint onto the mask, when they don't paint cells.
+    pub mask_tool: Option<Mask>,
+    /// The entry of `region_rules` selections are painted with.
+    pub region_rule: usize,
     pub show_info: bool,
     pub fps: Fps,
     pub font: Font,
-    pub rulestring: String,
+    /// The rule the board runs under, outside of its regions.
+    pub rule: Rule,
+    /// Rules the board has run under, most recent first.
+    pub rule_history: Vec<Rule>,
+    /// The rulestring being typed in, while the editor is open.
+    pub rule_editor: Option<RuleEditor>,
+    /// The rules tried so far, while exploring.
+    pub explorer: Option<Explorer>,
+    /// How soups of the rule turned out, while it's shown.
+    pub classification: Option<Classification>,
+    /// Where the classification running in the background ends up.
+    pub classifying: Option<Arc<OnceLock<Result<Classification, String>>>>,
     pub selection: Option<Selection>,
     pub keybinds: String,
     pub show_keybinds: bool,
-    pub clipboard: Option<Grid<bool>>,
+    pub clipboard: Option<Grid<u8>>,
 }
 
 impl Model {
     pub fn delta_time(&self) -> f32 {
         1. / self.fps.avg() as f32
     }
+    /// Runs the board under `rule` from now on, in place of the continuous
+    /// automaton if there is one.
+    pub fn set_rule(&mut self, rule: Rule) -> Result<(), String> {
+        self.switch_rule(rule)?;
+        self.record_rule();
+        Ok(())
+    }
+    /// Like [`Model::set_rule`], but leaves the history alone, for rules
+    /// that are only being tried out.
+    pub fn switch_rule(&mut self, rule: Rule) -> Result<(), String> {
+        self.board.change_rule(&self.rule, &rule)?;
+
+        self.field = None;
+        self.cache.hexagonal = rule.neighbourhood() == Neighbourhood::Hexagonal;
+        self.paint_state = default_paint_state(&rule);
+        self.rule = rule;
+        Ok(())
+    }
+    /// Puts the current rule at the top of the history.
+    pub fn record_rule(&mut self) {
+        self.rule_history.retain(|old| old != &self.rule);
+        self.rule_history.insert(0, self.rule.clone());
+        self.rule_history.truncate(RULE_HISTORY);
+    }
 }
 
-#[derive(Parser, Debug)]
-#[command(version, about, long_about = None)]
-struct Args {
-    #[arg(short, long)]
-    load: Option<String>,
-
-    #[arg(short, long)]
-    print: Option<String>,
+/// The state left clicks paint with until another is picked.
+fn default_paint_state(rule: &Rule) -> u8 {
+    if rule.is_wireworld() {
+        CONDUCTOR
+    } else {
+        1
+    }
 }
 
 pub fn model(app: &App) -> Model {
@@ -48,6 +96,7 @@ pub fn model(app: &App) -> Model {
         .mouse_released(mouse_released)
         .resized(window_resized)
         .key_pressed(key_pressed)
+        .received_character(received_character)
         .mouse_moved(mouse_moved)
         .mouse_wheel(mouse_wheel)
         .build()
@@ -55,59 +104,80 @@ pub fn model(app: &App) -> Model {
 
     let initial_tile_size = CONFIG.tile_size;
 
-    let (mut board, width, height) = if CONFIG.autosize_board {
-        let rect = app.window_rect();
-        let width = (rect.w() / CONFIG.tile_size).ceil() as usize;
-        let height = (rect.h() / CONFIG.tile_size).ceil() as usize;
-
-        let board = Board::new(width, height);
-
-        (board, width, height)
-    } else {
-        (
-            Board::new(CONFIG.board_size.x, CONFIG.board_size.y),
-            CONFIG.board_size.x,
-            CONFIG.board_size.y,
-        )
-    };
+    let mut board = Board::new();
+    let mut rule = CONFIG.rule.clone();
 
     let args = Args::parse();
     let mut paused = false;
 
-    if args.load.is_some() {
-        board = load_savestate(args.load.unwrap());
-        board.set_wh(width, height);
-        paused = true;
-        notify_info("Savestate loaded.");
+    if let Some(id) = args.load {
+        match load_savestate(&id) {
+            Ok((loaded, loaded_rule)) => {
+                board = loaded;
+                // Savestates from before rules were saved run under the
+                // configured one.
+                rule = loaded_rule.unwrap_or(rule);
+                paused = true;
+                notify_info("Savestate loaded.");
+            }
+            Err(err) => notify_error(err),
+        }
     }
 
-    if args.print.is_some() {
-        let board = load_savestate(args.print.unwrap());
-        board.print();
+    if let Some(id) = args.print {
+        match load_savestate(&id) {
+            Ok((board, _)) => board.print(),
+            Err(err) => eprintln!("{}", err),
+        }
         app.quit();
     }
 
+    let field = match CONFIG.continuous_rule() {
+        Some(Ok(rule)) => {
+            let mut field = Field::new(rule.clone(), CONFIG.field_size);
+            field.randomize(&mut rand::thread_rng());
+            Some(field)
+        }
+        Some(Err(err)) => {
+            notify_error(err);
+            None
+        }
+        None => None,
+    };
+
     let mut model = Model {
         board,
+        field,
         paused,
         pressed: None,
         last_mouse_pos: (0., 0.),
-        cache: Cache::new((width, height), initial_tile_size),
+        cache: Cache::new(
+            initial_tile_size,
+            rule.neighbourhood() == Neighbourhood::Hexagonal,
+        ),
         mouse_pos: (0.0, 0.0),
         grid_lines: false,
         symmetry: false,
+        paint_state: default_paint_state(&rule),
+        mask_tool: None,
+        region_rule: 0,
         last_mouse_pressed: None,
         show_info: false,
         fps: Fps::default(),
         font: load_font(),
-        rulestring: CONFIG.rule.serialize(),
+        rule_history: vec![rule.clone()],
+        rule,
+        rule_editor: None,
+        explorer: None,
+        classification: None,
+        classifying: None,
         selection: None,
         keybinds: include_str!("../../assets/keybinds.txt").to_string(),
         show_keybinds: false,
         clipboard: None,
     };
 
-    model.cache.update((width, height), CONFIG.tile_size);
+    model.cache.update(CONFIG.tile_size);
 
     notify_info("Press K to show keybinds");
 
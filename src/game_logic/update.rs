@@ -21,66 +21,79 @@ pub fn update(app: &App, model: &mut Model, _update: Update) {
         f(&mut cache.camera_offset.1, cache.target_camera_offset.1);
 
         if cache_needs_updating {
-            cache.update(model.board.wh(), cache.tile_size);
+            cache.update(cache.tile_size);
         }
 
         model.cache.tile_size = model.cache.tile_size * (1.0 - smoothing_factor)
             + model.cache.target_tile_size * smoothing_factor;
     }
 
+    if let Some(result) = model.classifying.as_ref().and_then(|result| result.get()) {
+        match result {
+            Ok(classification) => model.classification = Some(classification.clone()),
+            Err(err) => notify_error(err),
+        }
+        model.classifying = None;
+    }
+
     if !model.paused {
-        time!("advance", { model.board.advance() });
+        if let Some(field) = &mut model.field {
+            time!("advance", { field.step() });
+        } else {
+            time!("advance", { model.board.advance(&model.rule) });
+            if model.rule.wolfram().is_some() {
+                follow_row(model);
+            }
+        }
     }
     if let Some(button) = model.pressed {
-        if model.selection.is_none() {
-            let mut set = |to: bool| {
-                let pos = app.mouse.position();
-                let (x, y) = pixel_to_board(pos, &model.cache);
-
-                let (board_width, board_height) = model.board.wh();
-
-                let mut set_tile = |x, y, to| {
-                    model.board.set(x, y, to);
-
-                    if model.symmetry {
-                        let x_mirrored = board_width.saturating_sub(1 + x);
-                        model.board.set(x_mirrored, y, to);
-
-                        let y_mirrored = board_height.saturating_sub(1 + y);
-                        model.board.set(x, y_mirrored, to);
-
-                        model.board.set(x_mirrored, y_mirrored, to);
-                    }
-                };
-
-                if model
-                    .last_mouse_pressed
-                    .is_some_and(|last_button| button == last_button)
-                {
-                    let (px, py) = pixel_to_board(f32_to_vec2(model.last_mouse_pos), &model.cache);
-
-                    model.board.draw_line(x, y, px, py, to);
-                    if model.symmetry {
-                        let x_mirrored = (board_width).saturating_sub(1 + x);
-                        let px_mirrored = (board_width).saturating_sub(1 + px);
-                        let y_mirrored = (board_height).saturating_sub(1 + y);
-                        let py_mirrored = (board_height).saturating_sub(1 + py);
+        if let Some(field) = &mut model.field {
+            let (x, y) = pixel_to_board(app.mouse.position(), &model.cache);
+            match button {
+                MouseButton::Left => field.paint(x, y, 1.),
+                MouseButton::Right => field.paint(x, y, 0.),
+                _ => (),
+            }
+        } else if model.selection.is_none() {
+            let pos = app.mouse.position();
+            let (x, y) = pixel_to_board(pos, &model.cache);
+
+            // Drags paint a line back to where the mouse was last update.
+            let mut cells = if model
+                .last_mouse_pressed
+                .is_some_and(|last_button| button == last_button)
+            {
+                let (px, py) = pixel_to_board(f32_to_vec2(model.last_mouse_pos), &model.cache);
+                line_cells((x, y), (px, py))
+            } else {
+                vec![(x, y)]
+            };
+            if model.symmetry {
+                cells = cells
+                    .into_iter()
+                    .flat_map(|(x, y)| [(x, y), (-x, y), (x, -y), (-x, -y)])
+                    .collect();
+            }
 
-                        model.board.draw_line(x_mirrored, y, px_mirrored, py, to);
-                        model.board.draw_line(x, y_mirrored, px, py_mirrored, to);
-                        model
-                            .board
-                            .draw_line(x_mirrored, y_mirrored, px_mirrored, py_mirrored, to);
+            let board = &mut model.board;
+            // Emitters start on the generation they're painted on.
+            let tool = model.mask_tool.map(|tool| match tool {
+                Mask::Clock { period, .. } => Mask::Clock {
+                    period,
+                    phase: board.generation % period.max(1),
+                },
+                tool => tool,
+            });
+            for (x, y) in cells {
+                match (button, tool) {
+                    (MouseButton::Left, None) => board.set_state(x, y, model.paint_state),
+                    (MouseButton::Right, None) => board.set_state(x, y, 0),
+                    (MouseButton::Left, Some(tool)) => {
+                        board.set_mask(x, y, Some(tool), &model.rule)
                     }
-                } else {
-                    set_tile(x, y, to);
+                    (MouseButton::Right, Some(_)) => board.set_mask(x, y, None, &model.rule),
+                    _ => (),
                 }
-            };
-
-            match button {
-                MouseButton::Left => set(true),
-                MouseButton::Right => set(false),
-                _ => (),
             }
         }
 
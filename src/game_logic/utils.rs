@@ -1,29 +1,65 @@
 use crate::prelude::*;
 
-pub fn pixel_to_board(pixel: Vec2, cache: &Cache) -> (usize, usize) {
-    (
-        (((pixel.x - cache.camera_offset.0 * cache.scale_factor) / cache.tile_size)
-            + cache.half_board_width)
-            .round() as usize,
-        (((pixel.y - cache.camera_offset.1 * cache.scale_factor) / cache.tile_size)
-            + cache.half_board_height)
-            .round() as usize,
-    )
+pub fn pixel_to_board(pixel: Vec2, cache: &Cache) -> (i64, i64) {
+    let x = (pixel.x - cache.camera_offset.0 * cache.scale_factor) / cache.tile_size;
+    let y = (pixel.y - cache.camera_offset.1 * cache.scale_factor) / cache.tile_size;
+
+    if !cache.hexagonal {
+        return (x.round() as i64, y.round() as i64);
+    }
+
+    // Treat the cell as axial hex coordinates and round in cube coordinates,
+    // fixing up whichever of the three rounded the furthest.
+    let (q, r) = (x - y / 2., y);
+    let s = -q - r;
+    let (mut rq, mut rr, rs) = (q.round(), r.round(), s.round());
+    let (dq, dr, ds) = ((rq - q).abs(), (rr - r).abs(), (rs - s).abs());
+    if dq > dr && dq > ds {
+        rq = -rr - rs;
+    } else if dr > ds {
+        rr = -rq - rs;
+    }
+
+    (rq as i64, rr as i64)
+}
+
+pub fn board_xy_to_pixel(board: (i64, i64), cache: &Cache) -> (f32, f32) {
+    board_point_to_pixel((board.0 as f32, board.1 as f32), cache)
 }
 
-pub fn board_xy_to_pixel(board: (usize, usize), cache: &Cache) -> (f32, f32) {
-    let (x, y) = board;
+/// Like [`board_xy_to_pixel`], for points between cell centres.
+pub fn board_point_to_pixel((x, y): (f32, f32), cache: &Cache) -> (f32, f32) {
+    let x = if cache.hexagonal { x + y / 2. } else { x };
 
     (
-        (x as f32 - cache.half_board_width) * cache.tile_size
-            + (cache.camera_offset.0 * cache.scale_factor),
-        (y as f32 - cache.half_board_height) * cache.tile_size
-            + (cache.camera_offset.1 * cache.scale_factor),
+        x * cache.tile_size + (cache.camera_offset.0 * cache.scale_factor),
+        y * cache.tile_size + (cache.camera_offset.1 * cache.scale_factor),
     )
 }
 
-pub fn board_to_pixel(i: usize, cache: &Cache) -> (f32, f32) {
-    board_xy_to_pixel(i_to_xy(cache.board_width, i), cache)
+/// The outline of the cells from `min` to `max` (inclusive): bottom left,
+/// bottom right, top right and top left. A parallelogram on hex boards.
+pub fn area_corners(min: (i64, i64), max: (i64, i64), cache: &Cache) -> [Vec2; 4] {
+    let (x0, y0) = (min.0 as f32 - 0.5, min.1 as f32 - 0.5);
+    let (x1, y1) = (max.0 as f32 + 0.5, max.1 as f32 + 0.5);
+
+    [(x0, y0), (x1, y0), (x1, y1), (x0, y1)].map(|p| f32_to_vec2(board_point_to_pixel(p, cache)))
+}
+
+/// The corners of a cell drawn as a hexagon, going round from the top.
+pub fn hexagon(centre: (f32, f32), tile_size: f32) -> [Vec2; 6] {
+    let (x, y) = centre;
+    let (w, h) = (tile_size / 2., tile_size / 3.);
+
+    [
+        (0., 2. * h),
+        (w, h),
+        (w, -h),
+        (0., -2. * h),
+        (-w, -h),
+        (-w, h),
+    ]
+    .map(|(dx, dy)| Vec2::new(x + dx, y + dy))
 }
 
 pub fn f32_to_vec2(f: (f32, f32)) -> Vec2 {
@@ -34,39 +70,114 @@ pub fn vec2_to_f32(v: Vec2) -> (f32, f32) {
     (v.x, v.y)
 }
 
-pub fn i_to_xy(width: usize, i: usize) -> (usize, usize) {
-    (i % width, i / width)
-}
-
 pub fn clamp_camera(model: &mut Model) {
-    if CONFIG.autosize_board {
-        model.cache.target_tile_size = model
-            .cache
-            .target_tile_size
-            .clamp(CONFIG.tile_size / 2., 100.0);
-    } else {
-        model.cache.target_tile_size = model.cache.target_tile_size.clamp(1., 100.0);
-    }
+    model.cache.target_tile_size = model.cache.target_tile_size.clamp(1., 100.0);
+
+    // Keep the centre of the screen within reach of the pattern (or the origin).
+    let (min, max) = match model.board.topology.bounds() {
+        Some((min, max)) => (min.into(), max.into()),
+        None => model
+            .board
+            .bounds()
+            .unwrap_or((VecI2::new(0, 0), VecI2::new(0, 0))),
+    };
 
-    let f = |board_side: usize, value: &mut f32| {
-        let clamp_offset = (board_side as f32 * CONFIG.tile_size) / 2.;
-        *value = value.clamp(-clamp_offset, clamp_offset);
+    let f = |min: i64, max: i64, value: &mut f32| {
+        let margin = 50;
+        let lower = -(max.max(0) + margin) as f32 * CONFIG.tile_size;
+        let upper = -(min.min(0) - margin) as f32 * CONFIG.tile_size;
+        *value = value.clamp(lower, upper);
     };
 
-    f(model.board.width(), &mut model.cache.target_camera_offset.0);
-    f(
-        model.board.height(),
-        &mut model.cache.target_camera_offset.1,
-    );
+    // Hex rows are shifted by half a cell per row.
+    let (min_x, max_x) = if model.cache.hexagonal {
+        (min.x + min.y / 2, max.x + max.y / 2)
+    } else {
+        (min.x, max.x)
+    };
+
+    f(min_x, max_x, &mut model.cache.target_camera_offset.0);
+    f(min.y, max.y, &mut model.cache.target_camera_offset.1);
 }
 
-pub fn print_grid(grid: Grid<bool>) {
+/// Scrolls down with the space-time diagram of a 1D rule once its newest row
+/// gets near the bottom of the window.
+pub fn follow_row(model: &mut Model) {
+    let margin = 5.;
+    let cache = &mut model.cache;
+    let y = current_row(&model.board) as f32;
+
+    let bottom = -cache.camera_offset.1 * cache.scale_factor / cache.tile_size
+        - cache.window_size.1 / 2. / cache.tile_size;
+    if y < bottom + margin {
+        let centre = y - margin + cache.window_size.1 / 2. / cache.tile_size;
+        cache.target_camera_offset.1 = -centre * cache.tile_size / cache.scale_factor;
+    }
+}
+
+pub fn print_grid(grid: Grid<u8>) {
     let mut tiles = grid.clone();
     tiles.flip_rows();
     tiles.flip_cols();
 
     for r in tiles.iter_rows() {
-        r.for_each(|v| print!("{}", if *v { "#" } else { " " }));
+        r.for_each(|v| print!("{}", if *v != 0 { "#" } else { " " }));
         println!();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn caches() -> Vec<Cache> {
+        [false, true]
+            .into_iter()
+            .map(|hexagonal| {
+                let mut cache = Cache::new(12., hexagonal);
+                cache.camera_offset = (-37.5, 80.25);
+                cache.scale_factor = 1.5;
+                cache
+            })
+            .collect()
+    }
+
+    #[test]
+    fn cells_map_back_from_their_pixels() {
+        for cache in caches() {
+            for x in -20..20 {
+                for y in -20..20 {
+                    let pixel = f32_to_vec2(board_xy_to_pixel((x, y), &cache));
+                    assert_eq!(pixel_to_board(pixel, &cache), (x, y));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn pixels_near_the_centre_of_a_cell_pick_it() {
+        for cache in caches() {
+            // Well inside both the square and the hexagon.
+            let reach = cache.tile_size * 0.3;
+            for (x, y) in [(0, 0), (5, -3), (-7, 11)] {
+                let (px, py) = board_xy_to_pixel((x, y), &cache);
+                for (dx, dy) in [(reach, 0.), (-reach, 0.), (0., reach), (0., -reach)] {
+                    let pixel = Vec2::new(px + dx, py + dy);
+                    assert_eq!(pixel_to_board(pixel, &cache), (x, y));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn hex_rows_are_shifted_half_a_cell() {
+        let cache = Cache::new(10., true);
+        assert_eq!(board_xy_to_pixel((0, 2), &cache), (10., 20.));
+        assert_eq!(board_xy_to_pixel((-1, 1), &cache), (-5., 10.));
+        // Either side of the edge between two cells of a row, and above them
+        // in the cell of the next row that sits between them.
+        assert_eq!(pixel_to_board(Vec2::new(4., 2.), &cache), (0, 0));
+        assert_eq!(pixel_to_board(Vec2::new(6., 2.), &cache), (1, 0));
+        assert_eq!(pixel_to_board(Vec2::new(5., 6.), &cache), (0, 1));
+    }
+}
@@ -3,6 +3,7 @@ use crate::time;
 use crate::timing::clear_timers;
 use crate::ui::draw_notifications;
 use crate::ui::{draw_info, Window};
+use nannou::image;
 
 pub fn view(app: &App, model: &Model, frame: Frame) {
     let cache = &model.cache;
@@ -10,15 +11,22 @@ pub fn view(app: &App, model: &Model, frame: Frame) {
     time!("view", {
         let draw = app.draw();
         let board = &model.board;
-        draw.background().color(CONFIG.void_color.to_srgb());
+        draw_background(&draw, board, cache);
 
-        draw_background(&draw, cache);
+        match &model.field {
+            Some(field) => time!("cells", {
+                draw_field(app, &draw, field, cache);
+            }),
+            None => {
+                time!("cells", {
+                    draw_cells(&draw, board, &model.rule, cache);
+                });
+                draw_mask(&draw, board, cache);
+                draw_regions(&draw, board, cache);
 
-        time!("cells", {
-            draw_cells(&draw, board, cache);
-        });
-
-        draw_highlight(&draw, model);
+                draw_highlight(&draw, model);
+            }
+        }
 
         if model.grid_lines {
             time!("grid lines", {
@@ -42,59 +50,179 @@ pub fn view(app: &App, model: &Model, frame: Frame) {
             .build()
             .render(&draw, cache, model);
 
+        if let Some(classification) = &model.classification {
+            Window::new()
+                .text(classification.to_string())
+                .open(true)
+                .build()
+                .render(&draw, cache, model);
+        }
+
+        if let Some(editor) = &model.rule_editor {
+            editor.render(&draw, cache, model);
+        }
+
         clear_timers();
 
         draw.to_frame(app, &frame).unwrap();
     });
 }
 
-fn draw_background(draw: &Draw, cache: &Cache) {
-    draw.rect()
-        .x_y(
-            cache.camera_offset.0 * cache.scale_factor - 0.5 * cache.tile_size,
-            cache.camera_offset.1 * cache.scale_factor - 0.5 * cache.tile_size,
-        )
-        .w_h(
-            cache.board_width as f32 * cache.tile_size,
-            cache.board_height as f32 * cache.tile_size,
-        )
+fn draw_background(draw: &Draw, board: &Board, cache: &Cache) {
+    let Some((start, end)) = board.topology.bounds() else {
+        draw.background().color(CONFIG.background_color.to_srgb());
+        return;
+    };
+
+    draw.background().color(CONFIG.void_color.to_srgb());
+
+    draw.polygon()
+        .points(area_corners(start, end, cache))
         .color(CONFIG.background_color.to_srgb());
 }
 
-fn draw_cells(draw: &Draw, board: &Board, cache: &Cache) {
-    for (i, tile) in board.tiles.iter().enumerate() {
-        if *tile {
-            let (px, py) = board_to_pixel(i, cache);
+/// Fills one cell, as a square or a hexagon depending on the board.
+fn draw_cell(draw: &Draw, cache: &Cache, (x, y): (i64, i64), color: Srgb) {
+    let (px, py) = board_xy_to_pixel((x, y), cache);
+
+    if cache.hexagonal {
+        draw.polygon()
+            .points(hexagon((px, py), cache.tile_size))
+            .color(color);
+    } else {
+        draw.rect()
+            .x_y(px, py)
+            .w_h(cache.tile_size, cache.tile_size)
+            .color(color);
+    }
+}
 
-            draw.rect()
-                .x_y(px, py)
-                .w_h(cache.tile_size, cache.tile_size)
-                .color(CONFIG.cell_color.to_srgb());
+fn draw_cells(draw: &Draw, board: &Board, rule: &Rule, cache: &Cache) {
+    let ((min_x, max_x), (min_y, max_y)) = cache.visible_cells();
+    let (min_cx, max_cx) = (split_coord(min_x).0, split_coord(max_x).0);
+    let (min_cy, max_cy) = (split_coord(min_y).0, split_coord(max_y).0);
+
+    for (&(cx, cy), chunk) in &board.chunks {
+        if cx < min_cx || cx > max_cx || cy < min_cy || cy > max_cy {
+            continue;
+        }
+
+        for (x, y, state) in chunk.occupied() {
+            let (x, y) = (cx * CHUNK_SIZE + x as i64, cy * CHUNK_SIZE + y as i64);
+            let rule = board.region_at(x, y).map_or(rule, |region| &region.rule);
+            draw_cell(
+                draw,
+                cache,
+                (x, y),
+                CONFIG.state_color(rule, state).to_srgb(),
+            );
         }
     }
 }
 
+/// Draws walls and emitters over the cells, emitters dimmed while they're off.
+fn draw_mask(draw: &Draw, board: &Board, cache: &Cache) {
+    let ((min_x, max_x), (min_y, max_y)) = cache.visible_cells();
+
+    for (&(x, y), mask) in &board.mask {
+        if x < min_x || x > max_x || y < min_y || y > max_y {
+            continue;
+        }
+
+        let color = match mask {
+            Mask::On => CONFIG.wall_on_color,
+            Mask::Off => CONFIG.wall_off_color,
+            Mask::Clock { .. } if mask.state(board.generation) == 1 => CONFIG.clock_color,
+            Mask::Clock { .. } => CONFIG.clock_color.lerp(CONFIG.background_color, 0.6),
+        };
+        draw_cell(draw, cache, (x, y), color.to_srgb());
+    }
+}
+
+/// Tints every rule region, each rule on the board in its own colour.
+fn draw_regions(draw: &Draw, board: &Board, cache: &Cache) {
+    let mut rules: Vec<&Rule> = Vec::new();
+
+    for region in &board.regions {
+        let index = rules
+            .iter()
+            .position(|&rule| rule == &region.rule)
+            .unwrap_or_else(|| {
+                rules.push(&region.rule);
+                rules.len() - 1
+            });
+        let Some(color) = CONFIG
+            .region_colors
+            .get(index % CONFIG.region_colors.len().max(1))
+        else {
+            continue;
+        };
+
+        let color = color.to_srgb();
+        draw.polygon()
+            .points(area_corners(region.min, region.max, cache))
+            .color(srgba(
+                color.red,
+                color.green,
+                color.blue,
+                CONFIG.region_opacity,
+            ));
+    }
+}
+
+/// Draws a continuous field as one texture, a pixel per cell.
+fn draw_field(app: &App, draw: &Draw, field: &Field, cache: &Cache) {
+    let colors: Vec<[u8; 4]> = (0..=255)
+        .map(|i| {
+            let color = CONFIG.field_color(i as f32 / 255.).to_srgb();
+            let channel = |c: f32| (c * 255.).round() as u8;
+            [
+                channel(color.red),
+                channel(color.green),
+                channel(color.blue),
+                255,
+            ]
+        })
+        .collect();
+
+    // Images run top down, the board bottom up.
+    let size = field.size() as u32;
+    let origin = field.origin();
+    let image = image::RgbaImage::from_fn(size, size, |x, y| {
+        let value = field.get(origin + x as i64, origin + (size - 1 - y) as i64);
+        image::Rgba(colors[(value * 255.).round() as usize])
+    });
+    let texture = wgpu::Texture::from_image(app, &image::DynamicImage::ImageRgba8(image));
+
+    let centre = origin as f32 + (size as f32 - 1.) / 2.;
+    let (x, y) = board_point_to_pixel((centre, centre), cache);
+    let side = size as f32 * cache.tile_size;
+    draw.sampler(
+        wgpu::SamplerBuilder::new()
+            .mag_filter(wgpu::FilterMode::Nearest)
+            .into_descriptor(),
+    )
+    .texture(&texture)
+    .x_y(x, y)
+    .w_h(side, side);
+}
+
 fn draw_highlight(draw: &Draw, model: &Model) {
     let cache = &model.cache;
     let board = &model.board;
     let (x, y) = pixel_to_board(f32_to_vec2(model.mouse_pos), cache);
 
-    if let Some(v) = board.get(x, y) {
-        let (px, py) = board_xy_to_pixel((x, y), cache);
-        if v {
-            draw.rect()
-                .x_y(px, py)
-                .w_h(cache.tile_size, cache.tile_size)
-                .color(CONFIG.cell_color_highlighted.to_srgb());
-        } else {
-            draw.rect()
-                .x_y(px, py)
-                .w_h(cache.tile_size, cache.tile_size)
-                .color(CONFIG.background_color_highlighted.to_srgb());
-        }
-    }
+    let color = if board.get(x, y) {
+        CONFIG.cell_color_highlighted
+    } else {
+        CONFIG.background_color_highlighted
+    };
+
+    draw_cell(draw, cache, (x, y), color.to_srgb());
 }
 
+const MIN_HEX_GRID_TILE_SIZE: f32 = 6.;
+
 fn draw_grid_lines(draw: &Draw, cache: &Cache) {
     let mut weight = CONFIG.grid_thickness;
     let ts = cache.tile_size;
@@ -103,33 +231,49 @@ fn draw_grid_lines(draw: &Draw, cache: &Cache) {
         weight *= cache.scale_factor;
     }
 
-    let (x, y) = board_xy_to_pixel((0, 0), cache);
-    {
-        let mut x = x - 0.5 * ts;
-        let y = y - 0.5 * ts;
+    let ((min_x, max_x), (min_y, max_y)) = cache.visible_cells();
 
-        for i in 0..=cache.board_width {
-            draw.line()
-                .start(pt2(x, y))
-                .end(pt2(x, y + cache.board_height as f32 * ts))
-                .weight(if i % 10 == 0 { weight * 2. } else { weight })
-                .color(CONFIG.grid_color.to_srgb());
+    if cache.hexagonal {
+        // Too small to tell apart from a solid fill.
+        if ts < MIN_HEX_GRID_TILE_SIZE {
+            return;
+        }
 
-            x += ts;
+        for y in min_y..=max_y {
+            for x in min_x..=max_x {
+                let corners = hexagon(board_xy_to_pixel((x, y), cache), ts);
+                // The top, north-east and east edges; neighbours draw the rest.
+                draw.polyline()
+                    .weight(weight)
+                    .points([corners[5], corners[0], corners[1], corners[2]])
+                    .color(CONFIG.grid_color.to_srgb());
+            }
         }
+        return;
     }
 
-    {
+    let (start_x, start_y) = board_xy_to_pixel((min_x, min_y), cache);
+    let (end_x, end_y) = board_xy_to_pixel((max_x, max_y), cache);
+
+    for i in min_x..=max_x {
+        let (x, _) = board_xy_to_pixel((i, 0), cache);
         let x = x - 0.5 * ts;
-        let mut y = y - 0.5 * ts;
-        for i in 0..=cache.board_width {
-            draw.line()
-                .start(pt2(x, y))
-                .end(pt2(x + cache.board_height as f32 * ts, y))
-                .weight(if i % 10 == 0 { weight * 2. } else { weight })
-                .color(CONFIG.grid_color.to_srgb());
-
-            y += ts;
-        }
+
+        draw.line()
+            .start(pt2(x, start_y - 0.5 * ts))
+            .end(pt2(x, end_y + 0.5 * ts))
+            .weight(if i % 10 == 0 { weight * 2. } else { weight })
+            .color(CONFIG.grid_color.to_srgb());
+    }
+
+    for i in min_y..=max_y {
+        let (_, y) = board_xy_to_pixel((0, i), cache);
+        let y = y - 0.5 * ts;
+
+        draw.line()
+            .start(pt2(start_x - 0.5 * ts, y))
+            .end(pt2(end_x + 0.5 * ts, y))
+            .weight(if i % 10 == 0 { weight * 2. } else { weight })
+            .color(CONFIG.grid_color.to_srgb());
     }
 }
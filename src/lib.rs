@@ -0,0 +1,32 @@
+//! The game of life and its relatives. The binary is a thin wrapper around
+//! [`run`]; depend on the library instead to [`game::register`] automata of
+//! your own before running it.
+
+use clap::Parser;
+use cli::Args;
+use game_logic::{model, update, view};
+
+mod cli;
+mod config;
+mod favourites;
+pub mod game;
+mod game_logic;
+mod prelude;
+mod savestates;
+mod timing;
+mod ui;
+mod utils;
+
+/// Runs the command given on the command line, or opens the window if there
+/// isn't one.
+pub fn run() {
+    if let Some(command) = Args::parse().command {
+        if let Err(err) = command.run() {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    nannou::app(model).update(update).view(view).run();
+}
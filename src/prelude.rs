@@ -1,4 +1,5 @@
 pub use crate::config::*;
+pub use crate::favourites::*;
 pub use crate::game::*;
 pub use crate::game_logic::*;
 pub use crate::savestates::*;
@@ -1,55 +1,148 @@
-use crate::{game::Board, utils::BASE_DIR};
+use crate::{
+    game::{Board, Mask, Region, Rule, Topology},
+    utils::{VecI2, BASE_DIR},
+};
 use bitvec::prelude::*;
 use chrono::{Datelike, Local, Timelike};
 use grid::Grid;
-use serde::ser::SerializeStruct;
-use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
 
-impl Serialize for Board {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: Serializer,
-    {
-        let mut board = self.clone();
-        board.crop();
-        let width = board.width();
-
-        let mut bv: BitVec<u8, Lsb0> = BitVec::new();
-        board.tiles.iter().for_each(|v| bv.push(*v));
-
-        let mut state = serializer.serialize_struct("Board", 4)?;
-        state.serialize_field("width", &width)?;
-        state.serialize_field("tiles", &bv)?;
-        state.end()
-    }
+/// What savestates start with, before the layout of the rest. Older files
+/// start with the width of the board instead, which never comes near this.
+const MAGIC: &[u8; 8] = b"GOLSTATE";
+
+/// The layouts savestates are written in, tagged so that loading knows which
+/// one it's reading. Changing what's saved means adding a variant and
+/// loading the old ones into the new.
+#[derive(Serialize, Deserialize)]
+enum Layout {
+    V1(SavedBoard),
 }
 
-impl<'de> Deserialize<'de> for Board {
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-    where
-        D: Deserializer<'de>,
-    {
-        #[derive(Deserialize)]
-        struct BoardData {
-            width: usize,
-            tiles: BitVec<u8, Lsb0>,
+/// A board and the rule it runs under, as saved.
+#[derive(Serialize, Deserialize)]
+struct SavedBoard {
+    width: usize,
+    /// Which tiles aren't dead, one row after another.
+    tiles: BitVec<u8, Lsb0>,
+    /// The state of each tile that isn't dead, or nothing if they're all 1.
+    states: Vec<u8>,
+    generation: u64,
+    /// The lowest corner of the tiles.
+    origin: (i64, i64),
+    topology: Topology,
+    seed: u64,
+    regions: Vec<Region>,
+    mask: HashMap<(i64, i64), Mask>,
+    /// The rule outside of the regions.
+    rule: Rule,
+}
+
+impl SavedBoard {
+    fn new(board: &Board, rule: &Rule) -> Self {
+        let tiles = board.to_grid();
+        let origin = board.bounds().map_or((0, 0), |(min, _)| (min.x, min.y));
+
+        // Only multi-state boards need the state of every occupied cell.
+        let states = if tiles.iter().any(|v| *v > 1) {
+            tiles.iter().copied().filter(|v| *v != 0).collect()
+        } else {
+            Vec::new()
+        };
+
+        SavedBoard {
+            width: tiles.cols(),
+            tiles: tiles.iter().map(|v| *v != 0).collect(),
+            states,
+            generation: board.generation,
+            origin,
+            topology: board.topology,
+            seed: board.seed,
+            regions: board.regions.clone(),
+            mask: board.mask.clone(),
+            rule: rule.clone(),
         }
+    }
+    /// Puts the tiles back where they were and on the same generation, so
+    /// block rules keep their partition, 1D rules their current row and B0
+    /// rules how the board is stored.
+    fn into_board(self) -> (Board, Rule) {
+        let mut board = Board::new();
+        board.topology = self.topology;
+        board.generation = self.generation;
+        board.seed = self.seed;
+        board.regions = self.regions;
+        board.mask = self.mask;
+        board.set_area(
+            VecI2::new(self.origin.0, self.origin.1),
+            &decode(self.width, self.tiles, self.states),
+        );
 
-        let mut data = BoardData::deserialize(deserializer)?;
+        (board, self.rule)
+    }
+}
 
-        let tiles = (0..data.tiles.len())
-            .map(|_| data.tiles.pop().unwrap())
-            .rev()
-            .collect::<Vec<bool>>();
-        let mut grid = Grid::from_vec(tiles, data.width);
+/// The states of the tiles, in the order they were saved.
+fn decode(width: usize, tiles: BitVec<u8, Lsb0>, states: Vec<u8>) -> Grid<u8> {
+    let mut states = states.into_iter();
+
+    Grid::from_vec(
+        tiles
+            .iter()
+            .map(|v| match *v {
+                true => states.next().unwrap_or(1),
+                false => 0,
+            })
+            .collect(),
+        width,
+    )
+}
+
+/// Savestates from before they were tagged: the width and the tiles, then
+/// the states of the tiles if the board had any. They're centred on the
+/// origin.
+fn from_untagged(mut bytes: &[u8]) -> Result<Board, bincode::Error> {
+    let width: usize = bincode::deserialize_from(&mut bytes)?;
+    let tiles: BitVec<u8, Lsb0> = bincode::deserialize_from(&mut bytes)?;
+
+    let grid = if bytes.is_empty() {
+        // The first savestates were always loaded turned half a turn.
+        let mut grid = decode(width, tiles, Vec::new());
         grid.rotate_half();
+        grid
+    } else {
+        let states: Vec<u8> = bincode::deserialize_from(&mut bytes)?;
+        decode(width, tiles, states)
+    };
+
+    Ok(Board::from_grid(grid))
+}
 
-        Ok(Board { tiles: grid })
+/// A savestate of `board` running under `rule`.
+fn to_bytes(board: &Board, rule: &Rule) -> Vec<u8> {
+    let layout = Layout::V1(SavedBoard::new(board, rule));
+    let mut bytes = MAGIC.to_vec();
+    bincode::serialize_into(&mut bytes, &layout).expect("savestates always serialize");
+    bytes
+}
+
+/// The board in a savestate, and the rule it ran under if it was saved.
+fn from_bytes(bytes: &[u8]) -> Result<(Board, Option<Rule>), bincode::Error> {
+    match bytes.strip_prefix(MAGIC) {
+        Some(layout) => match bincode::deserialize(layout)? {
+            Layout::V1(saved) => {
+                let (board, rule) = saved.into_board();
+                Ok((board, Some(rule)))
+            }
+        },
+        None => Ok((from_untagged(bytes)?, None)),
     }
 }
 
-pub fn save_board(board: Board) {
+/// Saves `board` and `rule` under the current time.
+pub fn save_board(board: &Board, rule: &Rule) -> Result<(), String> {
     let time = Local::now();
     let id = format!(
         "{}-{}-{} {}:{}",
@@ -59,18 +152,131 @@ pub fn save_board(board: Board) {
         time.hour(),
         time.minute()
     );
-    let serialized = bincode::serialize(&board).unwrap();
-    fs::create_dir_all(savestate_dir()).unwrap();
-    fs::write(format!("{}/{}.gol", savestate_dir(), id), serialized)
-        .unwrap_or_else(|e| eprintln!("Failed to save board state: {}", e));
+
+    fs::create_dir_all(savestate_dir())
+        .and_then(|_| fs::write(savestate_path(&id), to_bytes(board, rule)))
+        .map_err(|err| format!("Failed to save board state: {}", err))
 }
 
-pub fn load_savestate(id: String) -> Board {
-    let text = fs::read(format!("{}/{}.gol", savestate_dir(), id)).unwrap();
+/// The board saved under `id`, and the rule it ran under unless it's from
+/// before rules were saved.
+pub fn load_savestate(id: &str) -> Result<(Board, Option<Rule>), String> {
+    let bytes = fs::read(savestate_path(id))
+        .map_err(|err| format!("Failed to read savestate '{}': {}", id, err))?;
+
+    from_bytes(&bytes).map_err(|err| format!("Savestate '{}' is corrupt: {}", id, err))
+}
 
-    bincode::deserialize(&text).unwrap()
+fn savestate_path(id: &str) -> String {
+    format!("{}/{}.gol", savestate_dir(), id)
 }
 
 fn savestate_dir() -> String {
     BASE_DIR.to_string() + "/savestates"
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn old_savestates_keep_states_on_their_cells() {
+        // The layout savestates had once cells had states, before the
+        // generation was saved.
+        #[derive(Serialize)]
+        struct BoardData {
+            width: usize,
+            tiles: BitVec<u8, Lsb0>,
+            states: Vec<u8>,
+        }
+
+        let grid = [[2, 1, 0], [0, 0, 3]];
+        let tiles = grid.iter().flatten().map(|&state| state != 0).collect();
+        let states = grid.iter().flatten().copied().filter(|&s| s != 0).collect();
+        let bytes = bincode::serialize(&BoardData {
+            width: 3,
+            tiles,
+            states,
+        })
+        .unwrap();
+
+        let (board, rule) = from_bytes(&bytes).unwrap();
+        assert_eq!(rule, None);
+        for (y, row) in grid.iter().enumerate() {
+            for (x, &state) in row.iter().enumerate() {
+                assert_eq!(board.state(x as i64 - 1, y as i64 - 1), state);
+            }
+        }
+    }
+
+    #[test]
+    fn the_first_savestates_still_load() {
+        // Rows of `##.` then `..#`, which come back turned half a turn.
+        let tiles: BitVec<u8, Lsb0> = [true, true, false, false, false, true]
+            .into_iter()
+            .collect();
+        let bytes = bincode::serialize(&(3usize, tiles)).unwrap();
+
+        let (board, rule) = from_bytes(&bytes).unwrap();
+        assert_eq!(rule, None);
+        assert_eq!(
+            board.sorted_cells(),
+            vec![(-1, -1, 1), (0, 0, 1), (1, 0, 1)]
+        );
+    }
+
+    #[test]
+    fn saves_the_rule_and_where_cells_were() {
+        let rule: Rule = "B2/S/3:T40,30".parse().unwrap();
+        let mut board = Board::new();
+        board.topology = rule.topology.unwrap();
+        board.generation = 7;
+        board.set_state(-20, 3, 1);
+        board.set_state(19, -15, 2);
+
+        let (loaded, loaded_rule) = from_bytes(&to_bytes(&board, &rule)).unwrap();
+        assert_eq!(loaded_rule, Some(rule));
+        assert_eq!(loaded.generation, 7);
+        assert_eq!(loaded.topology, board.topology);
+        assert_eq!(loaded.seed, board.seed);
+        assert_eq!(loaded.sorted_cells(), vec![(-20, 3, 1), (19, -15, 2)]);
+    }
+
+    #[test]
+    fn masked_boards_survive_a_round_trip() {
+        let rule: Rule = "B3/S23".parse().unwrap();
+        let mut board = Board::new();
+        board.set_state(3, 4, 1);
+        board.set_state(4, 4, 1);
+        board.set_state(5, 4, 1);
+        board.set_mask(0, 0, Some(Mask::On), &rule);
+        board.set_mask(1, 0, Some(Mask::Off), &rule);
+        board.set_mask(
+            2,
+            0,
+            Some(Mask::Clock {
+                period: 4,
+                phase: 2,
+            }),
+            &rule,
+        );
+        board.advance(&rule);
+
+        let (mut loaded, _) = from_bytes(&to_bytes(&board, &rule)).unwrap();
+        assert_eq!(loaded.mask, board.mask);
+        assert_eq!(loaded.sorted_cells(), board.sorted_cells());
+        for _ in 0..6 {
+            board.advance(&rule);
+            loaded.advance(&rule);
+            assert_eq!(loaded.sorted_cells(), board.sorted_cells());
+        }
+    }
+
+    #[test]
+    fn corrupt_savestates_are_errors() {
+        let bytes = to_bytes(&Board::new(), &"B3/S23".parse().unwrap());
+        assert!(from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        assert!(from_bytes(&[]).is_err());
+        assert!(from_bytes(&[1, 2, 3]).is_err());
+    }
+}
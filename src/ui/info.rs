@@ -12,19 +12,37 @@ pub fn draw_info(draw: &Draw, model: &Model) {
 
     if model.show_info {
         text = format!(
-        "{}\ngrid: ({} x {})\nwindow: ({} x {})\nrulestring: {}\ncamera offset: ({:.1} x {:.1})\nzoom: {:.2}",
+        "{}\npopulation: {}\nactive chunks: {} / {}\ngeneration: {}\nwindow: ({} x {})\nrulestring: {}\ntopology: {}\ncamera offset: ({:.1} x {:.1})\nzoom: {:.2}",
         text,
-        model.cache.board_width,
-        model.cache.board_height,
+        fmt_num(model.board.population()),
+        fmt_num(model.board.active_chunks),
+        fmt_num(model.board.chunks.len()),
+        fmt_num(model.board.generation as usize),
         model.cache.window_size.0,
         model.cache.window_size.1,
-        model.rulestring,
+        model.rule.serialize(),
+        model.board.topology.serialize(),
         model.cache.camera_offset.0,
         model.cache.camera_offset.1,
         model.cache.scale_factor
     );
     }
 
+    if let (true, Some(field)) = (model.show_info, &model.field) {
+        text = format!(
+            "{}\n\ncontinuous: {}\nmass: {:.1}\nfield generation: {}",
+            text,
+            CONFIG.continuous.as_deref().unwrap_or_default(),
+            field.mass(),
+            fmt_num(field.generation as usize)
+        );
+    }
+
+    if let Some(explorer) = &model.explorer {
+        let (candidate, candidates) = explorer.position();
+        text = format!("{}\nExploring rule {} of {}", text, candidate, candidates);
+    }
+
     if model.symmetry {
         text = format!("{}\nSymmetry on", text);
     }
@@ -33,6 +51,10 @@ pub fn draw_info(draw: &Draw, model: &Model) {
         text = format!("{}\nGrid on", text);
     }
 
+    if model.rule.states() > 2 {
+        text = format!("{}\nPainting state {}", text, model.paint_state);
+    }
+
     #[cfg(debug_assertions)]
     {
         if model.show_info {
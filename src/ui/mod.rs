@@ -1,7 +1,9 @@
 pub use self::info::*;
 pub use self::notifications::*;
+pub use self::rule_editor::*;
 pub use self::windows::*;
 
 mod info;
 mod notifications;
+mod rule_editor;
 mod windows;
@@ -23,6 +23,7 @@ pub fn notify_info(text: impl Display) {
     send_notification(text, NotificationKind::Info);
 }
 
+#[allow(dead_code)]
 pub fn notify(text: impl Display) {
     send_notification(text, NotificationKind::Default);
 }
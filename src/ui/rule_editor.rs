@@ -0,0 +1,97 @@
+use super::Window;
+use crate::prelude::*;
+
+/// How many rules the editor remembers.
+pub const RULE_HISTORY: usize = 10;
+
+const PROMPT: &str = "Rule: ";
+
+/// A text box for typing a rulestring to switch the running board to,
+/// checked as it's typed.
+#[derive(Clone, Debug, Default)]
+pub struct RuleEditor {
+    pub input: String,
+    /// What the input parses to, `None` while it's empty.
+    pub parsed: Option<Result<Rule, RuleError>>,
+    /// The entry of the history the input was recalled from, if it was.
+    recalled: Option<usize>,
+}
+
+impl RuleEditor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn push(&mut self, ch: char) {
+        if !ch.is_control() {
+            self.input.push(ch);
+            self.recalled = None;
+            self.parse();
+        }
+    }
+    pub fn backspace(&mut self) {
+        self.input.pop();
+        self.recalled = None;
+        self.parse();
+    }
+    /// Replaces the input with the next older rule in `history`, or the next
+    /// newer one, most recent first.
+    pub fn recall(&mut self, history: &[Rule], older: bool) {
+        let recalled = match (self.recalled, older) {
+            (None, true) => 0,
+            (None, false) => return,
+            (Some(i), true) => (i + 1).min(history.len().saturating_sub(1)),
+            (Some(0), false) => {
+                self.recalled = None;
+                self.input.clear();
+                self.parse();
+                return;
+            }
+            (Some(i), false) => i - 1,
+        };
+        let Some(rule) = history.get(recalled) else {
+            return;
+        };
+
+        self.input = rule.serialize();
+        self.recalled = Some(recalled);
+        self.parse();
+    }
+    fn parse(&mut self) {
+        let input = self.input.trim();
+        self.parsed = (!input.is_empty()).then(|| input.parse());
+    }
+    pub fn render(&self, draw: &Draw, cache: &Cache, model: &Model) {
+        let mut text = format!("{}{}_\n", PROMPT, self.input);
+
+        match &self.parsed {
+            None => text.push_str("Type a rulestring or a name like HighLife."),
+            Some(Ok(rule)) => text.push_str(&format!("= {}", rule.serialize())),
+            Some(Err(err)) => {
+                // The font is monospaced, so this lines up with the input.
+                if let Some(position) = err.position {
+                    let leading = self.input.len() - self.input.trim_start().len();
+                    let column = self.input[..leading + position].chars().count();
+                    text.push_str(&" ".repeat(PROMPT.len() + column));
+                    text.push_str("^ ");
+                }
+                text.push_str(&err.kind.to_string());
+            }
+        }
+
+        text.push_str("\n\nRecent rules (Up/Down):");
+        for (i, rule) in model.rule_history.iter().enumerate() {
+            let marker = if self.recalled == Some(i) { ">" } else { " " };
+            text.push_str(&format!("\n {} {}", marker, rule.serialize()));
+        }
+        text.push_str("\n\nEnter: Switch to the rule. Esc: Cancel.");
+
+        // Room for a long error to wrap onto a second line.
+        let lines = text.lines().count() + 1;
+        Window::new()
+            .text(text)
+            .lines(lines)
+            .open(true)
+            .build()
+            .render(draw, cache, model);
+    }
+}
@@ -1,5 +1,3 @@
-use serde::{Deserialize, Deserializer, Serialize, Serializer};
-
 #[cfg(not(debug_assertions))]
 use dirs::data_dir;
 use lazy_static::lazy_static;
@@ -27,46 +25,20 @@ pub fn load_font() -> Font {
     Font::from_bytes(asset).unwrap()
 }
 
-#[derive(Clone, Copy)]
-pub struct VecU2 {
-    pub x: usize,
-    pub y: usize,
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VecI2 {
+    pub x: i64,
+    pub y: i64,
 }
 
-impl VecU2 {
-    pub fn new(x: usize, y: usize) -> Self {
+impl VecI2 {
+    pub fn new(x: i64, y: i64) -> Self {
         Self { x, y }
     }
-    pub fn as_tuple(&self) -> (usize, usize) {
-        (self.x, self.y)
-    }
-}
-
-impl Serialize for VecU2 {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: Serializer,
-    {
-        serializer.serialize_str(&format!("{}x{}", self.x, self.y))
-    }
-}
-
-impl<'de> Deserialize<'de> for VecU2 {
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-    where
-        D: Deserializer<'de>,
-    {
-        let s = String::deserialize(deserializer)?;
-        let parts: Vec<&str> = s.split('x').collect();
-        Ok(VecU2 {
-            x: parts[0].parse().unwrap(),
-            y: parts[1].parse().unwrap(),
-        })
-    }
 }
 
-impl From<(usize, usize)> for VecU2 {
-    fn from(tuple: (usize, usize)) -> Self {
+impl From<(i64, i64)> for VecI2 {
+    fn from(tuple: (i64, i64)) -> Self {
         Self {
             x: tuple.0,
             y: tuple.1,